@@ -0,0 +1,72 @@
+use dada_ir::{
+    arguments::{match_arguments, ArgumentMismatch},
+    code::{Expr, ExprData},
+    definition::Definition,
+    diagnostic::{Diagnostic, Diagnostics},
+    func::Function,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Reports calls to classes, functions, and intrinsics whose named arguments
+/// do not match the fields or parameters that the callee declares.
+pub(crate) fn check_call_arguments(db: &dyn crate::Db, filename: Word, function: Function) {
+    let ast = function.ast(db);
+    let spans = function.spans(db);
+    let resolutions = dada_resolve::resolve_names(db, function);
+    let exprs = || (0..spans.expr_spans.len()).map(Expr::from);
+
+    let report = |span, message: String| {
+        Diagnostics::push(
+            db,
+            Diagnostic {
+                filename,
+                span,
+                message,
+                labels: vec![],
+            },
+        )
+    };
+
+    for expr in exprs() {
+        let (callee, arguments) = match &ast.tables[expr] {
+            ExprData::Call(callee, arguments) => (*callee, arguments),
+            _ => continue,
+        };
+        // Calls through local variables cannot be checked.
+        let (callee_name, parameters): (&str, Vec<Word>) = match resolutions.definition(callee) {
+            Some(Definition::Class(class)) => (
+                class.name(db).as_str(db),
+                class.fields(db).iter().map(|f| f.name(db)).collect(),
+            ),
+            Some(Definition::Function(function)) => (
+                function.name(db).as_str(db),
+                function.parameters(db).iter().map(|p| p.name(db)).collect(),
+            ),
+            Some(Definition::Intrinsic(intrinsic)) => (
+                intrinsic.str(),
+                intrinsic
+                    .parameters()
+                    .iter()
+                    .map(|name| Word::from(db, name))
+                    .collect(),
+            ),
+            _ => continue,
+        };
+
+        let names: Vec<Word> = arguments
+            .iter()
+            .map(|&argument| ast.tables[argument].name)
+            .collect();
+        for mismatch in match_arguments(&parameters, &names) {
+            let span = match mismatch {
+                ArgumentMismatch::UnknownParameter { index }
+                | ArgumentMismatch::Repeated { index } => {
+                    spans.named_expr_spans[arguments[index]].name_span
+                }
+                ArgumentMismatch::Missing { .. } => spans[expr],
+            };
+            report(span, mismatch.message(db, callee_name, &names));
+        }
+    }
+}
@@ -9,9 +9,18 @@ pub fn check_filename(db: &dyn crate::Db, filename: Word) {
     for &item in items {
         match item {
             Item::Function(function) => {
+                function.parameters(db);
                 function.ast(db);
+                crate::names::check_names(db, filename, function);
+                crate::arguments::check_call_arguments(db, filename, function);
+                crate::control_flow::check_control_flow(db, filename, function);
+                crate::types::infer_types(db, function);
+                crate::permissions::check_permissions(db, filename, function);
+            }
+            Item::Class(class) => {
+                class.fields(db);
+                crate::types::check_field_tys(db, filename, class);
             }
-            Item::Class(_class) => {}
         }
     }
 }
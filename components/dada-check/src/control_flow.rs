@@ -0,0 +1,164 @@
+use dada_ir::{
+    code::{Ast, Expr, ExprData, Spans},
+    diagnostic::{Diagnostic, Diagnostics},
+    func::Function,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Reports `break` and `continue` outside of a loop, `break` with a value
+/// inside of a `while` loop (which always produces `()`), and `return` with
+/// a value from `main` (whose result is discarded). A closure body starts
+/// outside of any loop, and `return` inside of it returns from the closure.
+pub(crate) fn check_control_flow(db: &dyn crate::Db, filename: Word, function: Function) {
+    let checker = ControlFlowChecker {
+        db,
+        filename,
+        function,
+        ast: function.ast(db),
+        spans: function.spans(db),
+    };
+    for &expr in &checker.ast.tables[checker.ast.block].exprs {
+        checker.check(expr, Context::default());
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum LoopKind {
+    Loop,
+    While,
+}
+
+/// Where an expression executes.
+#[derive(Copy, Clone, Default)]
+struct Context {
+    innermost_loop: Option<LoopKind>,
+    in_closure: bool,
+}
+
+struct ControlFlowChecker<'me> {
+    db: &'me dyn crate::Db,
+    filename: Word,
+    function: Function,
+    ast: &'me Ast,
+    spans: &'me Spans,
+}
+
+impl ControlFlowChecker<'_> {
+    /// Checks `expr`, which executes in `context`.
+    fn check(&self, expr: Expr, context: Context) {
+        let check = |expr: Expr| self.check(expr, context);
+        let in_loop = |kind| Context {
+            innermost_loop: Some(kind),
+            ..context
+        };
+        match &self.ast.tables[expr] {
+            ExprData::Loop(body) => self.check(*body, in_loop(LoopKind::Loop)),
+
+            ExprData::While(condition, body) => {
+                check(*condition);
+                self.check(*body, in_loop(LoopKind::While));
+            }
+
+            ExprData::Closure(_, _, body) => self.check(
+                *body,
+                Context {
+                    innermost_loop: None,
+                    in_closure: true,
+                },
+            ),
+
+            ExprData::Break(value) => {
+                match (context.innermost_loop, value) {
+                    (None, _) => self.report(expr, "`break` outside of a loop"),
+                    (Some(LoopKind::While), Some(value)) => {
+                        self.report(*value, "`break` with a value is only allowed inside `loop`")
+                    }
+                    (Some(_), _) => {}
+                }
+                if let Some(value) = *value {
+                    check(value);
+                }
+            }
+
+            ExprData::Continue => {
+                if context.innermost_loop.is_none() {
+                    self.report(expr, "`continue` outside of a loop");
+                }
+            }
+
+            ExprData::Return(value) => {
+                if let Some(value) = *value {
+                    if !context.in_closure && self.function.name(self.db).as_str(self.db) == "main"
+                    {
+                        self.report(value, "`main` cannot return a value");
+                    }
+                    check(value);
+                }
+            }
+
+            ExprData::Id(_)
+            | ExprData::StringLiteral(_)
+            | ExprData::NumberLiteral(_)
+            | ExprData::Error => {}
+
+            ExprData::Dot(base, _) => check(*base),
+
+            ExprData::Await(expr)
+            | ExprData::Share(expr)
+            | ExprData::Lease(expr)
+            | ExprData::Give(expr)
+            | ExprData::Var(_, _, expr)
+            | ExprData::Parenthesized(expr)
+            | ExprData::Atomic(expr)
+            | ExprData::Unary(_, expr) => check(*expr),
+
+            ExprData::Concatenate(exprs) | ExprData::Tuple(exprs) | ExprData::List(exprs) => {
+                for &expr in exprs {
+                    check(expr);
+                }
+            }
+
+            ExprData::Call(callee, arguments) => {
+                check(*callee);
+                for &argument in arguments {
+                    check(self.ast.tables[argument].expr);
+                }
+            }
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                check(*condition);
+                check(*then_expr);
+                if let Some(else_expr) = *else_expr {
+                    check(else_expr);
+                }
+            }
+
+            ExprData::Block(block) => {
+                for &expr in &self.ast.tables[*block].exprs {
+                    check(expr);
+                }
+            }
+
+            ExprData::Index(lhs, rhs)
+            | ExprData::Op(lhs, _, rhs)
+            | ExprData::OpEq(lhs, _, rhs)
+            | ExprData::Assign(lhs, rhs) => {
+                check(*lhs);
+                check(*rhs);
+            }
+        }
+    }
+
+    fn report(&self, expr: Expr, message: &str) {
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                filename: self.filename,
+                span: self.spans[expr],
+                message: message.to_string(),
+                labels: vec![],
+            },
+        );
+    }
+}
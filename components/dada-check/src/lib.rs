@@ -1,19 +1,37 @@
 #![allow(incomplete_features)]
 #![feature(trait_upcasting)]
 
+mod arguments;
 mod check;
+mod control_flow;
+mod names;
+mod permissions;
+mod types;
 
 #[salsa::jar(Db)]
-pub struct Jar(check::check_filename);
+pub struct Jar(check::check_filename, types::infer_types);
 
 pub trait Db:
-    salsa::DbWithJar<Jar> + dada_ir::Db + dada_lex::Db + dada_parse::Db + dada_manifest::Db
+    salsa::DbWithJar<Jar>
+    + dada_ir::Db
+    + dada_lex::Db
+    + dada_parse::Db
+    + dada_manifest::Db
+    + dada_resolve::Db
+    + dada_lower::Db
 {
 }
 
 impl<T> Db for T where
-    T: salsa::DbWithJar<Jar> + dada_ir::Db + dada_lex::Db + dada_parse::Db + dada_manifest::Db
+    T: salsa::DbWithJar<Jar>
+        + dada_ir::Db
+        + dada_lex::Db
+        + dada_parse::Db
+        + dada_manifest::Db
+        + dada_resolve::Db
+        + dada_lower::Db
 {
 }
 
 pub use check::check_filename;
+pub use types::{infer_types, Types};
@@ -0,0 +1,26 @@
+use dada_ir::{
+    code::ExprData,
+    diagnostic::{Diagnostic, Diagnostics},
+    func::Function,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Reports the names in the body of `function` that refer to nothing.
+pub(crate) fn check_names(db: &dyn crate::Db, filename: Word, function: Function) {
+    let ast = function.ast(db);
+    let spans = function.spans(db);
+    for &expr in &dada_resolve::resolve_names(db, function).unresolved {
+        if let ExprData::Id(name) = ast.tables[expr] {
+            Diagnostics::push(
+                db,
+                Diagnostic {
+                    filename,
+                    span: spans[expr],
+                    message: format!("unresolved name `{}`", name.as_str(db)),
+                    labels: vec![],
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,636 @@
+use dada_collections::{IndexVec, Map, Set};
+use dada_ir::{
+    cfg::{
+        BasicBlock, Cfg, Local, LocalOrigin, Place, Projection, Rvalue, Statement, StatementKind,
+        Terminator,
+    },
+    code::Spans,
+    definition::Definition,
+    diagnostic::{Diagnostic, Diagnostics, Label},
+    func::Function,
+    item::Item,
+    span::Span,
+    storage_mode::StorageMode,
+    ty::Permission,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Reports uses of variables that would fail when the function runs because
+/// of how their values were given, leased or shared:
+///
+/// * using a variable after its value was given away;
+/// * writing to a field through a shared value;
+/// * using a variable while a unique lease of it is still live, or
+///   modifying or dropping it while any lease of it is still live.
+///
+/// A lease is live as long as some variable holding it (or something
+/// leased from it) may still be used. Values whose permission is not
+/// known, such as those returned by functions, are never reported.
+pub(crate) fn check_permissions(db: &dyn crate::Db, filename: Word, function: Function) {
+    let cfg = dada_lower::lower_function(db, function);
+
+    // A callee is given to the call from the temporary that the item was
+    // stored into, which comes first in the same block.
+    let mut items = Map::default();
+    for block in cfg.blocks.iter() {
+        for statement in &block.statements {
+            match &statement.kind {
+                StatementKind::Assign(place, Rvalue::Item(definition)) => {
+                    items.insert(place.local, *definition);
+                }
+                StatementKind::Assign(place, Rvalue::Give(source))
+                    if source.projections.is_empty() && cfg.locals[place.local].name.is_none() =>
+                {
+                    if let Some(&definition) = items.get(&source.local) {
+                        items.insert(place.local, definition);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut atomic_fields = Set::default();
+    for &item in dada_parse::parse_file(db, filename) {
+        if let Item::Class(class) = item {
+            for &field in class.fields(db) {
+                if field.mode(db) == StorageMode::Atomic {
+                    atomic_fields.insert(field.name(db));
+                }
+            }
+        }
+    }
+
+    let mut checker = PermissionChecker {
+        db,
+        filename,
+        cfg,
+        spans: function.spans(db),
+        live_after: live_after(cfg),
+        items,
+        atomic_fields,
+        reporting: false,
+    };
+
+    let mut start_state = State::default();
+    for (local, data) in cfg.locals.iter().enumerate() {
+        if let LocalOrigin::Parameter(parameter) = data.origin {
+            let permission = parameter.ty(db).and_then(|ty| ty.strip_permission(db).0);
+            let value = Value {
+                permission,
+                ..Value::default()
+            };
+            start_state.values.insert(Local::from(local), value);
+        }
+    }
+
+    let entry_states = checker.entry_states(start_state);
+    checker.reporting = true;
+    for (block, state) in entry_states.into_iter().enumerate() {
+        if let Some(mut state) = state {
+            checker.check_block(BasicBlock::from(block), &mut state);
+        }
+    }
+}
+
+/// A statement that creates a lease, identified by its position.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+struct Loan {
+    block: BasicBlock,
+    statement: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum LoanKind {
+    Unique,
+
+    /// A unique lease stored into a variable declared without `var`,
+    /// which shares it.
+    Shared,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Access {
+    Read,
+    Write,
+    Drop,
+}
+
+/// What is known about the values of the locals at some point.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+struct State {
+    values: Map<Local, Value>,
+
+    /// Locals whose value may have been given away, and where.
+    given: Map<Local, Span>,
+
+    loan_kinds: Map<Loan, LoanKind>,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+struct Value {
+    /// The permission of the value, if it is known.
+    permission: Option<Permission>,
+
+    /// For `our` and `shared` values, where the value was shared.
+    shared_by: Option<Label>,
+
+    /// The leases that may be the value's own permission.
+    own_loans: Set<Loan>,
+
+    /// The leases that the value may depend on, including `own_loans`.
+    loans: Set<Loan>,
+}
+
+impl State {
+    /// Merges `other` into `self`, returning true if `self` changed.
+    fn join(&mut self, other: &State) -> bool {
+        let old = self.clone();
+        for (&local, value) in &other.values {
+            match self.values.get_mut(&local) {
+                Some(old_value) => old_value.join(value),
+                None => {
+                    self.values.insert(local, value.clone());
+                }
+            }
+        }
+        for (&local, &span) in &other.given {
+            self.given.entry(local).or_insert(span);
+        }
+        for (&loan, &kind) in &other.loan_kinds {
+            let old_kind = self.loan_kinds.entry(loan).or_insert(kind);
+            if *old_kind != kind {
+                *old_kind = LoanKind::Unique;
+            }
+        }
+        *self != old
+    }
+}
+
+impl Value {
+    fn owned(permission: Permission) -> Self {
+        Value {
+            permission: Some(permission),
+            ..Value::default()
+        }
+    }
+
+    fn join(&mut self, other: &Value) {
+        if self.permission != other.permission {
+            self.permission = None;
+            self.shared_by = None;
+        }
+        self.own_loans.extend(&other.own_loans);
+        self.loans.extend(&other.loans);
+    }
+}
+
+struct PermissionChecker<'me> {
+    db: &'me dyn crate::Db,
+    filename: Word,
+    cfg: &'me Cfg,
+    spans: &'me Spans,
+
+    /// For each statement, the locals whose values may be used afterwards.
+    live_after: IndexVec<BasicBlock, Vec<Set<Local>>>,
+
+    /// The items stored into temporaries.
+    items: Map<Local, Definition>,
+
+    /// Names of the atomic fields of the classes in the file, which may be
+    /// written through shared values.
+    atomic_fields: Set<Word>,
+
+    /// False while computing the states, true while reporting errors.
+    reporting: bool,
+}
+
+impl PermissionChecker<'_> {
+    /// Computes the state on entry to each reachable block.
+    fn entry_states(&self, start_state: State) -> Vec<Option<State>> {
+        let mut states: Vec<Option<State>> = vec![None; self.cfg.blocks.len()];
+        states[usize::from(self.cfg.start)] = Some(start_state);
+        let mut worklist = vec![self.cfg.start];
+        while let Some(block) = worklist.pop() {
+            let mut state = states[usize::from(block)].clone().unwrap();
+            self.check_block(block, &mut state);
+            for successor in self.cfg.blocks[block].terminator.successors() {
+                let successor_state = &mut states[usize::from(successor)];
+                let changed = match successor_state {
+                    Some(successor_state) => successor_state.join(&state),
+                    None => {
+                        *successor_state = Some(state.clone());
+                        true
+                    }
+                };
+                if changed && !worklist.contains(&successor) {
+                    worklist.push(successor);
+                }
+            }
+        }
+        states
+    }
+
+    fn check_block(&self, block: BasicBlock, state: &mut State) {
+        for (index, statement) in self.cfg.blocks[block].statements.iter().enumerate() {
+            let loan = Loan {
+                block,
+                statement: index,
+            };
+            self.check_statement(statement, loan, state);
+        }
+    }
+
+    /// Applies `statement` to `state`. `loan` identifies the lease that the
+    /// statement may create.
+    fn check_statement(&self, statement: &Statement, loan: Loan, state: &mut State) {
+        let span = self.spans[statement.origin];
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                // The old value of a local being assigned cannot be used later.
+                let overwritten = place.projections.is_empty().then_some(place.local);
+                let value = self.rvalue(rvalue, loan, span, overwritten, state);
+
+                if place.projections.is_empty() {
+                    self.access(place.local, Access::Write, loan, span, overwritten, state);
+                    self.store(place.local, value, span, state);
+                } else {
+                    self.check_given(place.local, span, state);
+                    self.check_write_through_shared(place, span, state);
+                    self.access(place.local, Access::Write, loan, span, None, state);
+                    let owner = state.values.entry(place.local).or_default();
+                    owner.loans.extend(value.loans);
+                }
+            }
+
+            StatementKind::Drop(local) => {
+                self.access(*local, Access::Drop, loan, span, None, state);
+                state.values.remove(local);
+                state.given.remove(local);
+            }
+
+            StatementKind::StartAtomic | StatementKind::EndAtomic => {}
+        }
+    }
+
+    /// Computes the value of `rvalue`, reporting the accesses it makes.
+    fn rvalue(
+        &self,
+        rvalue: &Rvalue,
+        loan: Loan,
+        span: Span,
+        overwritten: Option<Local>,
+        state: &mut State,
+    ) -> Value {
+        match rvalue {
+            Rvalue::Give(place) => {
+                self.check_given(place.local, span, state);
+                let source = self.place_value(place, state);
+                match (place.projections.is_empty(), source.permission) {
+                    (true, Some(Permission::My)) => {
+                        self.access(place.local, Access::Read, loan, span, overwritten, state);
+                        if self.cfg.locals[place.local].name.is_some() {
+                            state.given.insert(place.local, span);
+                        }
+                        source
+                    }
+                    // Giving a leased value leases it again.
+                    (true, Some(Permission::Leased)) => {
+                        self.access(place.local, Access::Write, loan, span, overwritten, state);
+                        self.new_loan(source, loan, state)
+                    }
+                    _ => {
+                        self.access(place.local, Access::Read, loan, span, overwritten, state);
+                        source
+                    }
+                }
+            }
+
+            Rvalue::Lease(place) => {
+                self.check_given(place.local, span, state);
+                let source = self.place_value(place, state);
+                match (place.projections.is_empty(), source.permission) {
+                    (true, Some(Permission::My | Permission::Leased)) => {
+                        self.access(place.local, Access::Write, loan, span, overwritten, state);
+                        self.new_loan(source, loan, state)
+                    }
+                    _ => {
+                        self.access(place.local, Access::Read, loan, span, overwritten, state);
+                        source
+                    }
+                }
+            }
+
+            Rvalue::Share(place) => {
+                self.check_given(place.local, span, state);
+                self.access(place.local, Access::Read, loan, span, overwritten, state);
+                if place.projections.is_empty() {
+                    let name = self.name(place.local);
+                    let label = Label {
+                        span,
+                        message: format!("`{name}` was shared here"),
+                    };
+                    self.share(place.local, label, state);
+                }
+                self.place_value(place, state)
+            }
+
+            Rvalue::Unit | Rvalue::Number(_) => Value::owned(Permission::Our),
+
+            Rvalue::String(_) | Rvalue::Item(_) => Value::owned(Permission::My),
+
+            // Interpolated places are only read.
+            Rvalue::Concatenate(places) => {
+                for place in places {
+                    self.check_given(place.local, span, state);
+                    self.access(place.local, Access::Read, loan, span, overwritten, state);
+                }
+                Value::owned(Permission::My)
+            }
+
+            Rvalue::Tuple(locals) | Rvalue::List(locals) => self.containing(locals, state),
+
+            Rvalue::Closure(_, _, captures) => {
+                let locals: Vec<Local> = captures.iter().map(|&(_, local)| local).collect();
+                self.containing(&locals, state)
+            }
+
+            // Constructing an instance stores the arguments into its fields;
+            // nothing is known about what other calls return.
+            Rvalue::Call(callee, arguments) => match self.items.get(callee) {
+                Some(Definition::Class(_)) => {
+                    let locals: Vec<Local> = arguments.iter().map(|&(_, local)| local).collect();
+                    self.containing(&locals, state)
+                }
+                _ => Value::default(),
+            },
+
+            Rvalue::Await(_) | Rvalue::Unary(..) | Rvalue::Op(..) | Rvalue::Error => {
+                Value::default()
+            }
+        }
+    }
+
+    /// The value stored in `place`. Fields reached through a shared value
+    /// are shared; nothing is known about other fields.
+    fn place_value(&self, place: &Place, state: &State) -> Value {
+        let value = state.values.get(&place.local).cloned().unwrap_or_default();
+        if place.projections.is_empty() {
+            return value;
+        }
+        let permission = match value.permission {
+            Some(Permission::Our | Permission::Shared) => Some(Permission::Shared),
+            _ => None,
+        };
+        Value {
+            permission,
+            shared_by: value.shared_by.filter(|_| permission.is_some()),
+            own_loans: Set::default(),
+            loans: value.loans,
+        }
+    }
+
+    /// A new owned value that holds the values of `locals`.
+    fn containing(&self, locals: &[Local], state: &State) -> Value {
+        let mut value = Value::owned(Permission::My);
+        for local in locals {
+            if let Some(element) = state.values.get(local) {
+                value.loans.extend(&element.loans);
+            }
+        }
+        value
+    }
+
+    /// A unique lease, created by the statement `loan`, of the value `source`.
+    fn new_loan(&self, source: Value, loan: Loan, state: &mut State) -> Value {
+        state.loan_kinds.insert(loan, LoanKind::Unique);
+        let mut loans = source.loans;
+        loans.insert(loan);
+        Value {
+            permission: Some(Permission::Leased),
+            shared_by: None,
+            own_loans: std::iter::once(loan).collect(),
+            loans,
+        }
+    }
+
+    /// Stores `value` into `local`. Variables declared without `var` share
+    /// the values stored into them.
+    fn store(&self, local: Local, value: Value, span: Span, state: &mut State) {
+        state.values.insert(local, value);
+        state.given.remove(&local);
+        if self.cfg.locals[local].mode == StorageMode::Shared {
+            let name = self.name(local);
+            let label = Label {
+                span,
+                message: format!(
+                    "this value is shared because `{name}` was not declared with `var`"
+                ),
+            };
+            self.share(local, label, state);
+        }
+    }
+
+    /// Converts the value of `local` into a shared one in place.
+    fn share(&self, local: Local, label: Label, state: &mut State) {
+        let value = match state.values.get_mut(&local) {
+            Some(value) => value,
+            None => return,
+        };
+        let permission = match value.permission {
+            Some(Permission::My) => Permission::Our,
+            Some(Permission::Leased) => Permission::Shared,
+            _ => return,
+        };
+        value.permission = Some(permission);
+        value.shared_by = Some(label);
+        for &loan in &value.own_loans {
+            state.loan_kinds.insert(loan, LoanKind::Shared);
+        }
+    }
+
+    /// Reports a use of `local` after its value was given away.
+    fn check_given(&self, local: Local, span: Span, state: &State) {
+        if !self.reporting {
+            return;
+        }
+        if let Some(&given_span) = state.given.get(&local) {
+            let name = self.name(local);
+            self.report(
+                span,
+                format!("use of `{name}` after it was given away"),
+                Some(Label {
+                    span: given_span,
+                    message: format!("`{name}` was given away here"),
+                }),
+            );
+        }
+    }
+
+    /// Reports a write to a field or element of `place` when the value that
+    /// owns it is shared. Atomic fields may be written through shared values.
+    fn check_write_through_shared(&self, place: &Place, span: Span, state: &State) {
+        if !self.reporting {
+            return;
+        }
+        if let Some(Projection::Field(field)) = place.projections.first() {
+            if self.atomic_fields.contains(field) {
+                return;
+            }
+        }
+        let value = match state.values.get(&place.local) {
+            Some(value) => value,
+            None => return,
+        };
+        if let Some(permission @ (Permission::Our | Permission::Shared)) = value.permission {
+            let name = self.name(place.local);
+            self.report(
+                span,
+                format!("cannot write through `{name}`, which has the `{permission}` permission"),
+                value.shared_by.clone(),
+            );
+        }
+    }
+
+    /// Reports an access to the value of `local` that cancels a lease of it
+    /// which is still live afterwards: reads cancel unique leases, while
+    /// writes and drops cancel all leases. The old value of `overwritten`
+    /// is not live, even if the local is used later.
+    fn access(
+        &self,
+        local: Local,
+        access: Access,
+        loan: Loan,
+        span: Span,
+        overwritten: Option<Local>,
+        state: &State,
+    ) {
+        if !self.reporting || self.cfg.locals[local].name.is_none() {
+            return;
+        }
+
+        let live = &self.live_after[loan.block][loan.statement];
+        let mut conflicts: Vec<Loan> = live
+            .iter()
+            .filter(|&&holder| Some(holder) != overwritten)
+            .filter_map(|holder| state.values.get(holder))
+            .flat_map(|value| value.loans.iter().copied())
+            .filter(|&l| self.leased_local(l) == local)
+            .filter(|l| access != Access::Read || state.loan_kinds[l] == LoanKind::Unique)
+            .collect();
+        conflicts.sort();
+
+        if let Some(&conflict) = conflicts.first() {
+            let name = self.name(local);
+            let message = match access {
+                Access::Read => format!("cannot use `{name}` while it is leased"),
+                Access::Write => format!("cannot modify `{name}` while it is leased"),
+                Access::Drop => format!("`{name}` is dropped while it is still leased"),
+            };
+            let statement = &self.cfg.blocks[conflict.block].statements[conflict.statement];
+            self.report(
+                span,
+                message,
+                Some(Label {
+                    span: self.spans[statement.origin],
+                    message: format!("`{name}` was leased here"),
+                }),
+            );
+        }
+    }
+
+    /// The local whose value is leased by the statement `loan`.
+    fn leased_local(&self, loan: Loan) -> Local {
+        match &self.cfg.blocks[loan.block].statements[loan.statement].kind {
+            StatementKind::Assign(_, Rvalue::Give(place) | Rvalue::Lease(place)) => place.local,
+            kind => unreachable!("statement does not create a lease: {kind:?}"),
+        }
+    }
+
+    fn name(&self, local: Local) -> &str {
+        match self.cfg.locals[local].name {
+            Some(name) => name.as_str(self.db),
+            None => "temporary",
+        }
+    }
+
+    fn report(&self, span: Span, message: String, label: Option<Label>) {
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                filename: self.filename,
+                span,
+                message,
+                labels: label.into_iter().collect(),
+            },
+        );
+    }
+}
+
+/// Computes, for each statement, the locals whose current values may be
+/// used by a later statement. Assigning to a local ends the life of its
+/// old value; dropping a local does not count as a use.
+fn live_after(cfg: &Cfg) -> IndexVec<BasicBlock, Vec<Set<Local>>> {
+    let live_at_end = |block: BasicBlock, live_in: &IndexVec<BasicBlock, Set<Local>>| {
+        let terminator = &cfg.blocks[block].terminator;
+        let mut live: Set<Local> = terminator
+            .successors()
+            .into_iter()
+            .flat_map(|successor| live_in[successor].iter().copied())
+            .collect();
+        match *terminator {
+            Terminator::If(condition, ..) => {
+                live.insert(condition);
+            }
+            Terminator::Return => {
+                live.insert(cfg.return_local);
+            }
+            Terminator::Goto(_) | Terminator::Error => {}
+        }
+        live
+    };
+
+    let mut live_in: IndexVec<BasicBlock, Set<Local>> =
+        cfg.blocks.iter().map(|_| Set::default()).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..cfg.blocks.len()).rev().map(BasicBlock::from) {
+            let mut live = live_at_end(block, &live_in);
+            for statement in cfg.blocks[block].statements.iter().rev() {
+                live_before(statement, &mut live);
+            }
+            if live != live_in[block] {
+                live_in[block] = live;
+                changed = true;
+            }
+        }
+    }
+
+    (0..cfg.blocks.len())
+        .map(BasicBlock::from)
+        .map(|block| {
+            let statements = &cfg.blocks[block].statements;
+            let mut live = live_at_end(block, &live_in);
+            let mut live_after = vec![Set::default(); statements.len()];
+            for (index, statement) in statements.iter().enumerate().rev() {
+                live_after[index] = live.clone();
+                live_before(statement, &mut live);
+            }
+            live_after
+        })
+        .collect()
+}
+
+/// Updates `live`, the locals live after `statement`, to those live before it.
+fn live_before(statement: &Statement, live: &mut Set<Local>) {
+    if let StatementKind::Assign(place, rvalue) = &statement.kind {
+        if place.projections.is_empty() {
+            live.remove(&place.local);
+        } else {
+            live.extend(place.locals());
+        }
+        live.extend(rvalue.locals());
+    }
+}
@@ -0,0 +1,704 @@
+//! Type inference and checking for typed Dada.
+//!
+//! Dada is gradually typed: only fields and parameters can declare a type, and
+//! a value whose type is unknown is accepted anywhere. Two consequences are
+//! easy to miss. Local variables have no declared type, so a use of a local has
+//! the type inferred for its initializer (shared, unless the local was declared
+//! with `var`), but assigning it a value of another type is not an error; the
+//! local's type is unknown from then on (see [`TypeChecker::declared_ty`]). And
+//! since functions do not declare a return type, the type of a call to a function
+//! is unknown; only constructing an instance of a class has a known type.
+
+use dada_collections::Map;
+use dada_ir::{
+    class::Class,
+    code::{Ast, Expr, ExprData, Spans},
+    definition::{Definition, Resolutions},
+    diagnostic::{Diagnostic, Diagnostics},
+    func::Function,
+    item::Item,
+    number::Number,
+    op::Op,
+    span::Span,
+    storage_mode::StorageMode,
+    ty::{Permission, Ty, TyData},
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// The types inferred for the expressions in a function body. Dada is
+/// gradually typed, so expressions whose type is not known have no entry
+/// and are accepted anywhere.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Types {
+    pub expr_tys: Map<Expr, Ty>,
+}
+
+impl Types {
+    pub fn ty(&self, expr: Expr) -> Option<Ty> {
+        self.expr_tys.get(&expr).copied()
+    }
+}
+
+/// Built-in types, along with the number of type arguments that they take.
+const BUILTIN_TYS: &[(&str, usize)] = &[
+    ("Bool", 0),
+    ("Float", 0),
+    ("Int", 0),
+    ("List", 1),
+    ("String", 0),
+];
+
+/// Infers the types of the expressions in the body of `function`, reporting
+/// values whose type does not match the type that their use requires, such
+/// as an argument whose type differs from the type declared for its parameter.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn infer_types(db: &dyn crate::Db, function: Function) -> Types {
+    let filename = function.code(db).tokens(db).filename(db);
+    let mut checker = TypeChecker {
+        db,
+        filename,
+        ast: function.ast(db),
+        spans: function.spans(db),
+        resolutions: dada_resolve::resolve_names(db, function),
+        items: dada_parse::parse_file(db, filename),
+        local_tys: Map::default(),
+        types: Types::default(),
+    };
+    for &parameter in function.parameters(db) {
+        if let Some(ty) = parameter.ty(db) {
+            checker.check_declared_ty(ty, &[], parameter.name_span(db));
+        }
+    }
+    for &expr in &checker.ast.tables[checker.ast.block].exprs {
+        checker.infer(expr);
+    }
+    checker.types
+}
+
+/// Reports unknown types, and the wrong number of type arguments,
+/// in the types declared for the fields of `class`.
+pub(crate) fn check_field_tys(db: &dyn crate::Db, filename: Word, class: Class) {
+    let items = dada_parse::parse_file(db, filename);
+    for &field in class.fields(db) {
+        if let Some(ty) = field.ty(db) {
+            check_declared_ty(
+                db,
+                filename,
+                items,
+                ty,
+                class.type_parameters(db),
+                field.name_span(db),
+            );
+        }
+    }
+}
+
+/// Checks a type written in a declaration at `span`; `type_parameters` are
+/// the type parameters in scope.
+fn check_declared_ty(
+    db: &dyn crate::Db,
+    filename: Word,
+    items: &[Item],
+    ty: Ty,
+    type_parameters: &[Word],
+    span: Span,
+) {
+    let report = |message: String| {
+        Diagnostics::push(
+            db,
+            Diagnostic {
+                filename,
+                span,
+                message,
+                labels: vec![],
+            },
+        )
+    };
+    let (name, arguments) = match ty.data(db) {
+        TyData::Permission(_, ty) => {
+            return check_declared_ty(db, filename, items, *ty, type_parameters, span);
+        }
+        TyData::Named(name, arguments) => (*name, arguments),
+    };
+
+    let expected_arguments = if type_parameters.contains(&name) {
+        Some(0)
+    } else if let Some(&(_, count)) = BUILTIN_TYS.iter().find(|(n, _)| *n == name.as_str(db)) {
+        Some(count)
+    } else {
+        items.iter().find_map(|item| match item {
+            Item::Class(class) if class.name(db) == name => Some(class.type_parameters(db).len()),
+            _ => None,
+        })
+    };
+    match expected_arguments {
+        None => report(format!("unknown type `{}`", name.as_str(db))),
+        Some(count) if count != arguments.len() => report(format!(
+            "`{}` expects {count} type argument(s), found {}",
+            name.as_str(db),
+            arguments.len()
+        )),
+        Some(_) => {}
+    }
+
+    for &argument in arguments {
+        check_declared_ty(db, filename, items, argument, type_parameters, span);
+    }
+}
+
+struct TypeChecker<'me> {
+    db: &'me dyn crate::Db,
+    filename: Word,
+    ast: &'me Ast,
+    spans: &'me Spans,
+    resolutions: &'me Resolutions,
+    items: &'me [Item],
+
+    /// The type of each local variable, keyed by the `Var` expression declaring it,
+    /// as of the expression being checked. Locals whose type is unknown have no entry.
+    local_tys: Map<Expr, Ty>,
+
+    types: Types,
+}
+
+impl TypeChecker<'_> {
+    /// Infers the type of `expr` (and of the expressions within it), recording it if known.
+    fn infer(&mut self, expr: Expr) -> Option<Ty> {
+        let ty = self.infer_uncached(expr);
+        if let Some(ty) = ty {
+            self.types.expr_tys.insert(expr, ty);
+        }
+        ty
+    }
+
+    fn infer_uncached(&mut self, expr: Expr) -> Option<Ty> {
+        let db = self.db;
+        match &self.ast.tables[expr] {
+            ExprData::Id(_) => match self.resolutions.definition(expr)? {
+                Definition::Local(var) => self.local_tys.get(&var).copied(),
+                Definition::Parameter(parameter) => parameter.ty(db),
+                Definition::Class(_) | Definition::Function(_) | Definition::Intrinsic(_) => None,
+            },
+
+            ExprData::StringLiteral(_) => Some(self.builtin("String", vec![])),
+
+            ExprData::Concatenate(exprs) => {
+                for &expr in exprs {
+                    self.infer(expr);
+                }
+                Some(self.builtin("String", vec![]))
+            }
+
+            ExprData::NumberLiteral(Number::Integer(_)) => Some(self.builtin("Int", vec![])),
+            ExprData::NumberLiteral(Number::Float(_)) => Some(self.builtin("Float", vec![])),
+
+            ExprData::Dot(base, field) => {
+                let base_ty = self.infer(*base)?;
+                let (_, base_ty) = base_ty.strip_permission(db);
+                let (name, arguments) = match base_ty.data(db) {
+                    TyData::Named(name, arguments) => (name, arguments),
+                    TyData::Permission(..) => return None,
+                };
+                let class = self.class_named(*name)?;
+                if !class.fields(db).iter().any(|f| f.name(db) == *field) {
+                    self.report(
+                        expr,
+                        format!(
+                            "`{}` has no field named `{}`",
+                            name.as_str(db),
+                            field.as_str(db)
+                        ),
+                    );
+                    return None;
+                }
+                self.field_ty(class, *field, Some(arguments.as_slice()))
+            }
+
+            ExprData::Index(base, index) => {
+                let base_ty = self.infer(*base);
+                let index_ty = self.infer(*index);
+                self.expect(*index, index_ty, Some(self.builtin("Int", vec![])));
+                let (_, base_ty) = base_ty?.strip_permission(db);
+                match base_ty.data(db) {
+                    TyData::Named(name, arguments) if name.as_str(db) == "List" => {
+                        arguments.first().copied()
+                    }
+                    _ => None,
+                }
+            }
+
+            ExprData::Call(callee, arguments) => {
+                let arguments: Vec<(Word, Expr, Option<Ty>)> = arguments
+                    .iter()
+                    .map(|&argument| {
+                        let data = &self.ast.tables[argument];
+                        (data.name, data.expr, self.infer(data.expr))
+                    })
+                    .collect();
+                self.infer(*callee);
+                match self.resolutions.definition(*callee) {
+                    Some(Definition::Class(class)) => self.infer_construct(class, &arguments),
+                    Some(Definition::Function(function)) => {
+                        for &(name, expr, ty) in &arguments {
+                            let declared = function
+                                .parameters(db)
+                                .iter()
+                                .find(|p| p.name(db) == name)
+                                .and_then(|p| p.ty(db));
+                            self.expect(expr, ty, declared);
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+
+            ExprData::Share(place) => {
+                let (permission, ty) = self.infer(*place)?.strip_permission(db);
+                let permission = match permission {
+                    Some(Permission::My | Permission::Our) => Permission::Our,
+                    Some(Permission::Leased | Permission::Shared) => Permission::Shared,
+                    None => return Some(ty),
+                };
+                Some(TyData::Permission(permission, ty).intern(db))
+            }
+
+            ExprData::Lease(place) => {
+                let (permission, ty) = self.infer(*place)?.strip_permission(db);
+                let permission = match permission {
+                    Some(Permission::My | Permission::Leased) => Permission::Leased,
+                    Some(Permission::Our | Permission::Shared) => Permission::Shared,
+                    None => return Some(ty),
+                };
+                Some(TyData::Permission(permission, ty).intern(db))
+            }
+
+            ExprData::Give(expr) | ExprData::Parenthesized(expr) | ExprData::Atomic(expr) => {
+                self.infer(*expr)
+            }
+
+            ExprData::Var(mode, _, initializer) => {
+                if let Some(ty) = self.infer(*initializer) {
+                    let ty = self.local_ty(*mode, ty);
+                    self.local_tys.insert(expr, ty);
+                }
+                None
+            }
+
+            ExprData::Tuple(exprs) => {
+                for &expr in exprs {
+                    self.infer(expr);
+                }
+                None
+            }
+
+            ExprData::List(exprs) => {
+                let tys: Vec<Option<Ty>> = exprs.iter().map(|&expr| self.infer(expr)).collect();
+                let element_tys: Vec<Ty> = tys
+                    .into_iter()
+                    .map(|ty| ty.map(|ty| ty.strip_permission(db).1))
+                    .collect::<Option<_>>()?;
+                let (&first, rest) = element_tys.split_first()?;
+                if rest.iter().all(|&ty| ty == first) {
+                    Some(self.builtin("List", vec![first]))
+                } else {
+                    None
+                }
+            }
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                self.infer_condition(*condition);
+                let then_ty = self.infer(*then_expr);
+                let else_ty = else_expr.and_then(|else_expr| self.infer(else_expr));
+                if then_ty == else_ty {
+                    then_ty
+                } else {
+                    None
+                }
+            }
+
+            ExprData::While(condition, body) => {
+                self.infer_condition(*condition);
+                self.infer(*body);
+                None
+            }
+
+            ExprData::Loop(expr) | ExprData::Await(expr) => {
+                self.infer(*expr);
+                None
+            }
+
+            ExprData::Closure(_, parameters, body) => {
+                for &parameter in parameters {
+                    if let Some(ty) = parameter.ty(db) {
+                        self.check_declared_ty(ty, &[], parameter.name_span(db));
+                    }
+                }
+                self.infer(*body);
+                None
+            }
+
+            ExprData::Break(expr) | ExprData::Return(expr) => {
+                if let Some(expr) = *expr {
+                    self.infer(expr);
+                }
+                None
+            }
+
+            ExprData::Continue | ExprData::Error => None,
+
+            ExprData::Block(block) => {
+                let mut ty = None;
+                for &expr in &self.ast.tables[*block].exprs {
+                    ty = self.infer(expr);
+                }
+                ty
+            }
+
+            ExprData::Unary(op, operand) => {
+                let operand_ty = self.infer(*operand)?;
+                let (_, stripped) = operand_ty.strip_permission(db);
+                let allowed: &[&str] = match op {
+                    Op::Not => &["Bool"],
+                    _ => &["Int", "Float"],
+                };
+                if self.is_builtin_of(stripped, allowed) {
+                    Some(stripped)
+                } else {
+                    self.report(
+                        expr,
+                        format!("cannot apply `{op}` to `{}`", operand_ty.display(db)),
+                    );
+                    None
+                }
+            }
+
+            ExprData::Op(lhs, op, rhs) => {
+                let lhs_ty = self.infer(*lhs);
+                let rhs_ty = self.infer(*rhs);
+                self.infer_op(expr, lhs_ty, *op, rhs_ty)
+            }
+
+            ExprData::OpEq(place, op, rhs) => {
+                let place_ty = self.infer(*place);
+                let rhs_ty = self.infer(*rhs);
+                let ty = self.infer_op(expr, place_ty, *op, rhs_ty);
+                let declared = self.declared_ty(*place, place_ty);
+                self.expect(*rhs, ty, declared);
+                self.assign_local(*place, ty);
+                None
+            }
+
+            ExprData::Assign(place, rhs) => {
+                let place_ty = self.infer(*place);
+                let rhs_ty = self.infer(*rhs);
+                let declared = self.declared_ty(*place, place_ty);
+                self.expect(*rhs, rhs_ty, declared);
+                self.assign_local(*place, rhs_ty);
+                None
+            }
+        }
+    }
+
+    /// Checks the arguments to the constructor of `class` against the declared
+    /// types of its fields, and returns the type of the new instance. For a
+    /// class with type parameters, each must be inferable from an argument for
+    /// a field declared with exactly that type.
+    fn infer_construct(
+        &mut self,
+        class: Class,
+        arguments: &[(Word, Expr, Option<Ty>)],
+    ) -> Option<Ty> {
+        let db = self.db;
+        for &(name, expr, ty) in arguments {
+            let declared = self.field_ty(class, name, None);
+            self.expect(expr, ty, declared);
+        }
+
+        let mut type_arguments = vec![];
+        for &type_parameter in class.type_parameters(db) {
+            let argument = class.fields(db).iter().find_map(|field| {
+                let declared_as_parameter = match field.ty(db)?.data(db) {
+                    TyData::Named(name, tys) => *name == type_parameter && tys.is_empty(),
+                    TyData::Permission(..) => false,
+                };
+                if !declared_as_parameter {
+                    return None;
+                }
+                let &(_, _, ty) = arguments.iter().find(|(n, ..)| *n == field.name(db))?;
+                Some(ty?.strip_permission(db).1)
+            })?;
+            type_arguments.push(argument);
+        }
+        let ty = TyData::Named(class.name(db), type_arguments).intern(db);
+        Some(TyData::Permission(Permission::My, ty).intern(db))
+    }
+
+    fn infer_op(&mut self, expr: Expr, lhs: Option<Ty>, op: Op, rhs: Option<Ty>) -> Option<Ty> {
+        let db = self.db;
+        let bool_ty = self.builtin("Bool", vec![]);
+        match op {
+            Op::AndAnd | Op::OrOr => {
+                for ty in [lhs, rhs] {
+                    self.expect_at(expr, ty, bool_ty, || format!("operands of `{op}`"));
+                }
+                return Some(bool_ty);
+            }
+            Op::EqualEqual
+            | Op::NotEqual
+            | Op::LessThan
+            | Op::LessEqual
+            | Op::GreaterThan
+            | Op::GreaterEqual => {
+                if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+                    let (lhs, rhs) = (lhs.strip_permission(db).1, rhs.strip_permission(db).1);
+                    if lhs != rhs {
+                        self.report(
+                            expr,
+                            format!(
+                                "cannot compare `{}` with `{}`",
+                                lhs.display(db),
+                                rhs.display(db)
+                            ),
+                        );
+                    }
+                }
+                return Some(bool_ty);
+            }
+            _ => {}
+        }
+
+        let (lhs, rhs) = (lhs?.strip_permission(db).1, rhs?.strip_permission(db).1);
+        let allowed: &[&str] = match op {
+            Op::Plus => &["Int", "Float", "String"],
+            _ => &["Int", "Float"],
+        };
+        if lhs == rhs && self.is_builtin_of(lhs, allowed) {
+            Some(lhs)
+        } else {
+            self.report(
+                expr,
+                format!(
+                    "cannot apply `{op}` to `{}` and `{}`",
+                    lhs.display(db),
+                    rhs.display(db)
+                ),
+            );
+            None
+        }
+    }
+
+    /// The type that values stored into `place`, of type `place_ty`, must have.
+    /// Local variables can be assigned values of any type (see [`Self::assign_local`]);
+    /// only the types of parameters and fields are declared.
+    fn declared_ty(&self, place: Expr, place_ty: Option<Ty>) -> Option<Ty> {
+        match self.resolutions.definition(place) {
+            Some(Definition::Local(_)) => None,
+            _ => place_ty,
+        }
+    }
+
+    /// The type of a local variable declared with `mode` that holds a value of
+    /// type `ty`. A local declared without `var` shares the value stored into it,
+    /// turning `my` into `our` and `leased` into `shared`.
+    fn local_ty(&self, mode: StorageMode, ty: Ty) -> Ty {
+        let (permission, stripped) = ty.strip_permission(self.db);
+        let permission = match (mode, permission) {
+            (StorageMode::Shared, Some(Permission::My)) => Permission::Our,
+            (StorageMode::Shared, Some(Permission::Leased)) => Permission::Shared,
+            (_, Some(permission)) => permission,
+            (_, None) => return ty,
+        };
+        TyData::Permission(permission, stripped).intern(self.db)
+    }
+
+    /// Records that `place` was assigned a value of type `ty`. If `place` is a
+    /// local variable and `ty` is not the type it had, the local's type is
+    /// unknown from then on, so that later uses accept either value.
+    fn assign_local(&mut self, place: Expr, ty: Option<Ty>) {
+        let var = match self.resolutions.definition(place) {
+            Some(Definition::Local(var)) => var,
+            _ => return,
+        };
+        let mode = match self.ast.tables[var] {
+            ExprData::Var(mode, ..) => mode,
+            _ => return,
+        };
+        let ty = ty.map(|ty| self.local_ty(mode, ty));
+        if self.local_tys.get(&var).copied() != ty {
+            self.local_tys.remove(&var);
+        }
+    }
+
+    /// Infers the type of an `if` or `while` condition, which must be a `Bool`.
+    fn infer_condition(&mut self, condition: Expr) {
+        let ty = self.infer(condition);
+        let bool_ty = self.builtin("Bool", vec![]);
+        self.expect_at(condition, ty, bool_ty, || "condition".to_string());
+    }
+
+    /// Reports an error if `expr`, of type `actual`, cannot be used where
+    /// a value of type `expected` is required.
+    fn expect(&mut self, expr: Expr, actual: Option<Ty>, expected: Option<Ty>) {
+        let (actual, expected) = match (actual, expected) {
+            (Some(actual), Some(expected)) => (actual, expected),
+            _ => return,
+        };
+        if !self.is_compatible(actual, expected) {
+            self.report(
+                expr,
+                format!(
+                    "expected `{}`, found `{}`",
+                    expected.display(self.db),
+                    actual.display(self.db)
+                ),
+            );
+        }
+    }
+
+    /// Like [`Self::expect`], but the error is reported at `expr` on behalf of `what`.
+    fn expect_at(
+        &mut self,
+        expr: Expr,
+        actual: Option<Ty>,
+        expected: Ty,
+        what: impl FnOnce() -> String,
+    ) {
+        let actual = match actual {
+            Some(actual) => actual,
+            None => return,
+        };
+        if !self.is_compatible(actual, expected) {
+            self.report(
+                expr,
+                format!(
+                    "expected `{}` for {}, found `{}`",
+                    expected.display(self.db),
+                    what(),
+                    actual.display(self.db)
+                ),
+            );
+        }
+    }
+
+    /// True if a value of type `actual` can be used where `expected` is required:
+    /// the types must be the same once permissions are removed, and if both have a
+    /// permission, `actual`'s must satisfy `expected`'s.
+    fn is_compatible(&self, actual: Ty, expected: Ty) -> bool {
+        let (actual_permission, actual) = actual.strip_permission(self.db);
+        let (expected_permission, expected) = expected.strip_permission(self.db);
+        if let (Some(actual), Some(expected)) = (actual_permission, expected_permission) {
+            if !actual.satisfies(expected) {
+                return false;
+            }
+        }
+        match (actual.data(self.db), expected.data(self.db)) {
+            (TyData::Named(a, a_arguments), TyData::Named(e, e_arguments)) => {
+                a == e
+                    && a_arguments.len() == e_arguments.len()
+                    && a_arguments
+                        .iter()
+                        .zip(e_arguments)
+                        .all(|(&a, &e)| self.is_compatible(a, e))
+            }
+            _ => false,
+        }
+    }
+
+    /// The declared type of `field` in `class`, with the class's type parameters
+    /// replaced by `type_arguments`. Unknown if the field has no declared type,
+    /// or if it mentions a type parameter and `type_arguments` is `None`.
+    fn field_ty(&self, class: Class, field: Word, type_arguments: Option<&[Ty]>) -> Option<Ty> {
+        let db = self.db;
+        let ty = class
+            .fields(db)
+            .iter()
+            .find(|f| f.name(db) == field)?
+            .ty(db)?;
+        let type_parameters = class.type_parameters(db);
+        match type_arguments {
+            Some(type_arguments) if type_arguments.len() == type_parameters.len() => {
+                Some(self.substitute(ty, type_parameters, type_arguments))
+            }
+            _ if self.mentions(ty, type_parameters) => None,
+            _ => Some(ty),
+        }
+    }
+
+    fn substitute(&self, ty: Ty, parameters: &[Word], arguments: &[Ty]) -> Ty {
+        let db = self.db;
+        match ty.data(db) {
+            TyData::Permission(permission, ty) => {
+                let ty = self.substitute(*ty, parameters, arguments);
+                TyData::Permission(*permission, ty).intern(db)
+            }
+            TyData::Named(name, tys) if tys.is_empty() => {
+                match parameters.iter().position(|p| p == name) {
+                    Some(index) => arguments[index],
+                    None => ty,
+                }
+            }
+            TyData::Named(name, tys) => {
+                let tys = tys
+                    .iter()
+                    .map(|&ty| self.substitute(ty, parameters, arguments))
+                    .collect();
+                TyData::Named(*name, tys).intern(db)
+            }
+        }
+    }
+
+    fn mentions(&self, ty: Ty, parameters: &[Word]) -> bool {
+        match ty.data(self.db) {
+            TyData::Permission(_, ty) => self.mentions(*ty, parameters),
+            TyData::Named(name, tys) => {
+                parameters.contains(name) || tys.iter().any(|&ty| self.mentions(ty, parameters))
+            }
+        }
+    }
+
+    fn check_declared_ty(&self, ty: Ty, type_parameters: &[Word], span: Span) {
+        check_declared_ty(
+            self.db,
+            self.filename,
+            self.items,
+            ty,
+            type_parameters,
+            span,
+        );
+    }
+
+    fn class_named(&self, name: Word) -> Option<Class> {
+        self.items.iter().find_map(|item| match *item {
+            Item::Class(class) if class.name(self.db) == name => Some(class),
+            _ => None,
+        })
+    }
+
+    fn builtin(&self, name: &str, arguments: Vec<Ty>) -> Ty {
+        TyData::Named(Word::from(self.db, name), arguments).intern(self.db)
+    }
+
+    /// True if `ty` is one of the argument-less built-in types in `names`.
+    fn is_builtin_of(&self, ty: Ty, names: &[&str]) -> bool {
+        match ty.data(self.db) {
+            TyData::Named(name, arguments) => {
+                arguments.is_empty() && names.contains(&name.as_str(self.db))
+            }
+            TyData::Permission(..) => false,
+        }
+    }
+
+    fn report(&self, expr: Expr, message: String) {
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                filename: self.filename,
+                span: self.spans[expr],
+                message,
+                labels: vec![],
+            },
+        );
+    }
+}
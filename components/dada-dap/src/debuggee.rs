@@ -0,0 +1,346 @@
+//! Runs the program being debugged on its own thread. Whenever it stops,
+//! that thread answers queries about its state (stack, variables) until
+//! the client asks it to resume. Requests that need the program to be
+//! stopped fail right away while it is running, so the request loop
+//! never waits for the program.
+
+use std::{
+    collections::BTreeSet,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use dada_execute::{Observer, Stop};
+use dada_ir::{span::Offset, word::Word};
+use serde_json::{json, Value};
+
+use crate::transport::{ConsoleWriter, Output};
+
+/// The interpreter runs a single task at a time, so it is presented to the client as one thread.
+pub(crate) const THREAD_ID: i64 = 1;
+
+/// Handle through which the request loop talks to the program.
+pub(crate) struct Debuggee {
+    commands: Sender<Command>,
+
+    /// Set by the program's thread when it stops or exits, and by
+    /// [`Debuggee::resume`] when it is resumed.
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum State {
+    Running,
+
+    /// Waiting for commands.
+    Stopped,
+
+    Exited,
+}
+
+enum Command {
+    /// Answer a query, sending the body of the response back.
+    Query(Query, Sender<eyre::Result<Value>>),
+
+    Resume(Resume),
+}
+
+pub(crate) enum Query {
+    StackTrace,
+    Scopes { frame_id: usize },
+    Variables { reference: usize },
+}
+
+#[derive(Copy, Clone)]
+pub(crate) enum Resume {
+    Continue,
+    Next,
+    StepIn,
+}
+
+impl Debuggee {
+    /// Starts running `program`, stopping at `breakpoints` (which the client may
+    /// change while the program runs) and, if `stop_on_entry`, at its first statement.
+    pub(crate) fn launch(
+        program: PathBuf,
+        breakpoints: Arc<Mutex<BTreeSet<Offset>>>,
+        stop_on_entry: bool,
+        output: Arc<Output>,
+    ) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let state = Arc::new(Mutex::new(State::Running));
+        let program_state = state.clone();
+        std::thread::spawn(move || {
+            let result = run(
+                &program,
+                breakpoints,
+                stop_on_entry,
+                receiver,
+                &program_state,
+                &output,
+            );
+            *program_state.lock().unwrap() = State::Exited;
+            let exit_code = match result {
+                Ok(()) => 0,
+                Err(error) => {
+                    output.console("stderr", &format!("{error:?}\n"));
+                    1
+                }
+            };
+            output.event("exited", json!({ "exitCode": exit_code }));
+            output.event("terminated", json!({}));
+        });
+        Self { commands, state }
+    }
+
+    /// Asks the stopped program a question; fails if it is not stopped.
+    pub(crate) fn query(&self, query: Query) -> eyre::Result<Value> {
+        self.expect_stopped()?;
+        let (reply, answer) = mpsc::channel();
+        self.commands
+            .send(Command::Query(query, reply))
+            .map_err(|_| eyre::eyre!("the program has exited"))?;
+        answer
+            .recv()
+            .map_err(|_| eyre::eyre!("the program has exited"))?
+    }
+
+    /// Resumes the stopped program. Fails if it is running, rather than
+    /// applying `resume` once it next stops.
+    pub(crate) fn resume(&self, resume: Resume) -> eyre::Result<()> {
+        self.expect_stopped()?;
+        *self.state.lock().unwrap() = State::Running;
+        self.commands
+            .send(Command::Resume(resume))
+            .map_err(|_| eyre::eyre!("the program has exited"))
+    }
+
+    /// Fails unless the program is stopped. Only the request loop resumes the
+    /// program, so it cannot start running again before the command that follows.
+    fn expect_stopped(&self) -> eyre::Result<()> {
+        match *self.state.lock().unwrap() {
+            State::Stopped => Ok(()),
+            State::Running => eyre::bail!("the program is running"),
+            State::Exited => eyre::bail!("the program has exited"),
+        }
+    }
+}
+
+fn run(
+    program: &Path,
+    breakpoints: Arc<Mutex<BTreeSet<Offset>>>,
+    stop_on_entry: bool,
+    commands: Receiver<Command>,
+    state: &Mutex<State>,
+    output: &Output,
+) -> eyre::Result<()> {
+    let mut db = dada_db::Db::default();
+    let contents = std::fs::read_to_string(program)
+        .map_err(|error| eyre::eyre!("reading `{}`: {error}", program.display()))?;
+    let filename = Word::from(&db, program);
+    db.update_file(filename, contents);
+
+    let diagnostics = db.diagnostics(filename);
+    if !diagnostics.is_empty() {
+        for diagnostic in &diagnostics {
+            let message = dada_lex::format_diagnostic(&db, diagnostic);
+            output.console("stderr", &format!("{message}\n"));
+        }
+        eyre::bail!(
+            "not executing `{}` due to {} compilation error(s)",
+            program.display(),
+            diagnostics.len()
+        );
+    }
+
+    let mut debugger = Debugger {
+        db: &db,
+        filename,
+        program: program.display().to_string(),
+        commands,
+        state,
+        output,
+        breakpoints,
+        mode: if stop_on_entry {
+            Mode::Entry
+        } else {
+            Mode::Continue
+        },
+        references: vec![],
+    };
+    dada_execute::interpret_with_observer(
+        &db,
+        filename,
+        &mut ConsoleWriter { output },
+        &mut debugger,
+    )
+}
+
+struct Debugger<'a> {
+    db: &'a dada_db::Db,
+    filename: Word,
+    program: String,
+    commands: Receiver<Command>,
+    state: &'a Mutex<State>,
+    output: &'a Output,
+    breakpoints: Arc<Mutex<BTreeSet<Offset>>>,
+    mode: Mode,
+
+    /// What each `variablesReference` handed out during the current stop refers to:
+    /// the index of a stack frame, plus an object in that frame's heap graph
+    /// (or `None` for the frame's locals). Reference `n` is stored at index `n - 1`,
+    /// since `0` means "no children".
+    references: Vec<(usize, Option<usize>)>,
+}
+
+/// What the debugger does when the next statement is reached.
+enum Mode {
+    /// Stop at the first statement, reporting it as the entry point.
+    Entry,
+
+    /// Stop at the next statement.
+    StepInto,
+
+    /// Stop at the next statement whose call depth is at most the given depth.
+    StepOver(usize),
+
+    /// Stop only at breakpoints.
+    Continue,
+}
+
+impl Observer for Debugger<'_> {
+    fn before_statement(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let reason = match self.mode {
+            Mode::Entry => "entry",
+            Mode::StepInto => "step",
+            Mode::StepOver(depth) if stop.depth <= depth => "step",
+            Mode::StepOver(_) | Mode::Continue => {
+                if self.breakpoints.lock().unwrap().contains(&stop.span.start) {
+                    "breakpoint"
+                } else {
+                    return ControlFlow::Continue(());
+                }
+            }
+        };
+
+        self.references.clear();
+        *self.state.lock().unwrap() = State::Stopped;
+        self.output.event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        );
+
+        loop {
+            match self.commands.recv() {
+                Ok(Command::Query(query, reply)) => {
+                    let _ = reply.send(self.answer(stop, query));
+                }
+                Ok(Command::Resume(resume)) => {
+                    self.mode = match resume {
+                        Resume::Continue => Mode::Continue,
+                        Resume::Next => Mode::StepOver(stop.depth),
+                        Resume::StepIn => Mode::StepInto,
+                    };
+                    return ControlFlow::Continue(());
+                }
+                // The client has gone away, so stop the program.
+                Err(_) => return ControlFlow::Break(()),
+            }
+        }
+    }
+}
+
+impl Debugger<'_> {
+    fn answer(&mut self, stop: &Stop<'_>, query: Query) -> eyre::Result<Value> {
+        match query {
+            Query::StackTrace => {
+                // The client expects the innermost frame first; frame ids are
+                // indices into `stop.stack()`, which is outermost first.
+                let stack = stop.stack();
+                let frames: Vec<Value> = stack
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, frame)| {
+                        let position =
+                            dada_lex::line_column(self.db, self.filename, frame.span.start);
+                        json!({
+                            "id": index,
+                            "name": frame.function.name(self.db).as_str(self.db),
+                            "source": {
+                                "name": self.filename.as_str(self.db),
+                                "path": self.program,
+                            },
+                            "line": position.line,
+                            "column": position.column,
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "stackFrames": frames, "totalFrames": stack.len() }))
+            }
+
+            Query::Scopes { frame_id } => {
+                if frame_id >= stop.depth {
+                    eyre::bail!("no stack frame with id {frame_id}");
+                }
+                let reference = self.reference(frame_id, None);
+                Ok(json!({
+                    "scopes": [{
+                        "name": "Locals",
+                        "variablesReference": reference,
+                        "expensive": false,
+                    }],
+                }))
+            }
+
+            Query::Variables { reference } => {
+                let &(frame_id, object) = reference
+                    .checked_sub(1)
+                    .and_then(|index| self.references.get(index))
+                    .ok_or_else(|| eyre::eyre!("unknown variables reference {reference}"))?;
+                let graph = stop.frame_heap_graph(frame_id);
+                let entries = match object {
+                    Some(object) => graph.fields(object),
+                    None => graph.locals(),
+                };
+                let variables: Vec<Value> = entries
+                    .into_iter()
+                    .map(|entry| {
+                        let children = match entry.object {
+                            Some(object) => self.reference(frame_id, Some(object)),
+                            None => 0,
+                        };
+                        json!({
+                            "name": entry.name,
+                            "value": entry.value,
+                            "variablesReference": children,
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "variables": variables }))
+            }
+        }
+    }
+
+    /// Returns the `variablesReference` for `object` in the given frame
+    /// (or for the frame's locals, if `object` is `None`).
+    fn reference(&mut self, frame_id: usize, object: Option<usize>) -> usize {
+        let key = (frame_id, object);
+        let index = match self.references.iter().position(|&r| r == key) {
+            Some(index) => index,
+            None => {
+                self.references.push(key);
+                self.references.len() - 1
+            }
+        };
+        index + 1
+    }
+}
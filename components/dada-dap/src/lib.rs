@@ -0,0 +1,193 @@
+//! A server for the [Debug Adapter Protocol], which lets editors debug
+//! Dada programs by driving the interpreter.
+//!
+//! [Debug Adapter Protocol]: https://microsoft.github.io/debug-adapter-protocol/
+
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use dada_ir::{span::Offset, word::Word};
+use debuggee::{Debuggee, Query, Resume, THREAD_ID};
+use serde_json::{json, Value};
+use transport::Output;
+
+mod debuggee;
+mod transport;
+
+pub struct DapServer {
+    output: Arc<Output>,
+
+    /// Set by `launch`.
+    program: Option<PathBuf>,
+    stop_on_entry: bool,
+
+    /// Used to resolve breakpoints to the statements they stop at.
+    db: dada_db::Db,
+
+    /// Start offsets of the statements to stop before; shared with the
+    /// running program so that changes take effect immediately.
+    breakpoints: Arc<Mutex<BTreeSet<Offset>>>,
+
+    /// Started on `configurationDone`, once the client has sent its breakpoints.
+    debuggee: Option<Debuggee>,
+}
+
+impl DapServer {
+    pub fn new() -> Self {
+        Self {
+            output: Arc::new(Output::new()),
+            program: None,
+            stop_on_entry: false,
+            db: dada_db::Db::default(),
+            breakpoints: Arc::default(),
+            debuggee: None,
+        }
+    }
+
+    /// Handles requests read from stdin until the client disconnects.
+    pub fn main_loop(&mut self) -> eyre::Result<()> {
+        let stdin = std::io::stdin();
+        let mut reader = stdin.lock();
+        while let Some(message) = transport::read_message(&mut reader)? {
+            if message["type"] != "request" {
+                continue;
+            }
+            let command = message["command"].as_str().unwrap_or_default();
+            let result = self.handle(command, &message["arguments"]);
+            self.output.response(&message, result);
+
+            match command {
+                "initialize" => self.output.event("initialized", json!({})),
+                "disconnect" => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the body of the response to `command`.
+    fn handle(&mut self, command: &str, arguments: &Value) -> eyre::Result<Value> {
+        match command {
+            "initialize" => Ok(json!({ "supportsConfigurationDoneRequest": true })),
+
+            "launch" => {
+                let program = arguments["program"]
+                    .as_str()
+                    .ok_or_else(|| eyre::eyre!("`launch` requires a `program` to debug"))?;
+                self.program = Some(PathBuf::from(program));
+                self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                Ok(json!({}))
+            }
+
+            "setBreakpoints" => {
+                // Only a single file is ever executed, so the breakpoints are
+                // assumed to be in the program, whose text is read from `source`.
+                let path = arguments["source"]["path"]
+                    .as_str()
+                    .ok_or_else(|| eyre::eyre!("`setBreakpoints` requires a `source.path`"))?;
+                let filename = self.load(path)?;
+                let mut offsets = BTreeSet::new();
+                let breakpoints: Vec<Value> = arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                    .map(|line| {
+                        match dada_execute::breakpoint_statement(&self.db, filename, line as u32) {
+                            Some(span) => {
+                                offsets.insert(span.start);
+                                let line = dada_lex::line_column(&self.db, filename, span.start);
+                                json!({ "verified": true, "line": line.line })
+                            }
+                            None => json!({
+                                "verified": false,
+                                "line": line,
+                                "message": "no statement on or after this line",
+                            }),
+                        }
+                    })
+                    .collect();
+                *self.breakpoints.lock().unwrap() = offsets;
+                Ok(json!({ "breakpoints": breakpoints }))
+            }
+
+            "configurationDone" => {
+                let program = self
+                    .program
+                    .clone()
+                    .ok_or_else(|| eyre::eyre!("`launch` must come before `configurationDone`"))?;
+                self.debuggee = Some(Debuggee::launch(
+                    program,
+                    self.breakpoints.clone(),
+                    self.stop_on_entry,
+                    self.output.clone(),
+                ));
+                Ok(json!({}))
+            }
+
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+
+            "stackTrace" => self.debuggee()?.query(Query::StackTrace),
+
+            "scopes" => {
+                let frame_id = usize_argument(arguments, "frameId")?;
+                self.debuggee()?.query(Query::Scopes { frame_id })
+            }
+
+            "variables" => {
+                let reference = usize_argument(arguments, "variablesReference")?;
+                self.debuggee()?.query(Query::Variables { reference })
+            }
+
+            "continue" => {
+                self.debuggee()?.resume(Resume::Continue)?;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+
+            "next" => {
+                self.debuggee()?.resume(Resume::Next)?;
+                Ok(json!({}))
+            }
+
+            "stepIn" => {
+                self.debuggee()?.resume(Resume::StepIn)?;
+                Ok(json!({}))
+            }
+
+            "disconnect" => Ok(json!({})),
+
+            _ => eyre::bail!("unsupported request `{command}`"),
+        }
+    }
+
+    /// Reads the file at `path` into `self.db`, so that breakpoints in it can be resolved.
+    fn load(&mut self, path: &str) -> eyre::Result<Word> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| eyre::eyre!("reading `{path}`: {error}"))?;
+        let filename = Word::from(&self.db, path);
+        self.db.update_file(filename, contents);
+        Ok(filename)
+    }
+
+    fn debuggee(&self) -> eyre::Result<&Debuggee> {
+        self.debuggee
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("the program has not been launched"))
+    }
+}
+
+impl Default for DapServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn usize_argument(arguments: &Value, name: &str) -> eyre::Result<usize> {
+    arguments[name]
+        .as_u64()
+        .map(|value| value as usize)
+        .ok_or_else(|| eyre::eyre!("expected `{name}` to be a number"))
+}
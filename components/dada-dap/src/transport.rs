@@ -0,0 +1,115 @@
+//! Reading and writing DAP messages, which are JSON bodies preceded by a
+//! `Content-Length` header (the same framing as LSP).
+
+use std::{
+    io::{BufRead, Write},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+};
+
+use eyre::Context;
+use serde_json::{json, Value};
+
+/// Reads the next message, or returns `None` once the input is closed.
+pub(crate) fn read_message(reader: &mut impl BufRead) -> eyre::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = Some(
+                length
+                    .trim()
+                    .parse::<usize>()
+                    .with_context(|| format!("invalid header `{header}`"))?,
+            );
+        }
+    }
+
+    let content_length =
+        content_length.ok_or_else(|| eyre::eyre!("message without `Content-Length` header"))?;
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Writes messages to the client. Shared between the thread handling
+/// requests and the thread running the program, which sends events.
+pub(crate) struct Output {
+    stdout: Mutex<std::io::Stdout>,
+    seq: AtomicI64,
+}
+
+impl Output {
+    pub(crate) fn new() -> Self {
+        Self {
+            stdout: Mutex::new(std::io::stdout()),
+            seq: AtomicI64::new(1),
+        }
+    }
+
+    pub(crate) fn response(&self, request: &Value, result: eyre::Result<Value>) {
+        let mut message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                message["success"] = json!(true);
+                message["body"] = body;
+            }
+            Err(error) => {
+                message["success"] = json!(false);
+                message["message"] = json!(error.to_string());
+            }
+        }
+        self.send(message);
+    }
+
+    pub(crate) fn event(&self, event: &str, body: Value) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    /// Sends an `output` event, which the client shows in its debug console.
+    pub(crate) fn console(&self, category: &str, text: &str) {
+        self.event("output", json!({ "category": category, "output": text }));
+    }
+
+    fn send(&self, mut message: Value) {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::SeqCst));
+        let body = message.to_string();
+        let mut stdout = self.stdout.lock().unwrap();
+        // If the client has gone away there is nobody left to report errors to.
+        let _ = write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = stdout.flush();
+    }
+}
+
+/// Forwards whatever the program prints to the client as `output` events.
+pub(crate) struct ConsoleWriter<'a> {
+    pub(crate) output: &'a Output,
+}
+
+impl Write for ConsoleWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.console("stdout", &String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -4,8 +4,10 @@ use dada_ir::{diagnostic::Diagnostic, item::Item, word::Word};
     dada_check::Jar,
     dada_ir::Jar,
     dada_lex::Jar,
+    dada_lower::Jar,
     dada_manifest::Jar,
-    dada_parse::Jar
+    dada_parse::Jar,
+    dada_resolve::Jar
 )]
 #[derive(Default)]
 pub struct Db {
@@ -0,0 +1,39 @@
+//! Resolving breakpoints, which users give as line numbers, to the
+//! statements that the interpreter actually stops at.
+
+use dada_ir::{
+    code::Block,
+    func::Function,
+    item::Item,
+    span::{LineColumn, Span},
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Returns the span of the first statement in `filename` that starts on or
+/// after the beginning of `line` (1-based), or `None` if there is no such
+/// statement. A breakpoint on a blank line or a comment thus moves to the
+/// statement that follows it.
+pub fn breakpoint_statement(db: &dyn crate::Db, filename: Word, line: u32) -> Option<Span> {
+    let start = dada_lex::offset(db, filename, LineColumn { line, column: 1 });
+    dada_parse::parse_file(db, filename)
+        .iter()
+        .flat_map(|&item| match item {
+            Item::Function(function) => statement_spans(db, function),
+            Item::Class(_) => vec![],
+        })
+        .filter(|span| span.start >= start)
+        .min_by_key(|span| span.start)
+}
+
+/// The spans of the statements in every block of `function`, which are the
+/// expressions that [`Observer::before_statement`](crate::Observer::before_statement)
+/// is invoked for.
+fn statement_spans(db: &dyn crate::Db, function: Function) -> Vec<Span> {
+    let ast = function.ast(db);
+    let spans = function.spans(db);
+    (0..spans.block_spans.len())
+        .map(Block::from)
+        .flat_map(|block| ast.tables[block].exprs.iter().map(|&expr| spans[expr]))
+        .collect()
+}
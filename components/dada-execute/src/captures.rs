@@ -0,0 +1,135 @@
+use dada_ir::{
+    code::{Ast, Expr, ExprData},
+    definition::{Definition, Resolutions},
+    func::{Function, Parameter},
+    word::Word,
+};
+
+/// Returns the variables that `body`, the body of a closure in `function` with
+/// the given parameters, uses without declaring them itself, paired with the
+/// first expression that names each one. These are the local variables and
+/// parameters of the enclosing scopes that the closure captures when it is
+/// created; names that refer to items are not captured.
+pub(crate) fn free_variables(
+    db: &dyn crate::Db,
+    function: Function,
+    parameters: &[Parameter],
+    body: Expr,
+) -> Vec<(Word, Expr)> {
+    let mut walker = FreeVariables {
+        ast: function.ast(db),
+        resolutions: dada_resolve::resolve_names(db, function),
+        declared: parameters
+            .iter()
+            .copied()
+            .map(Definition::Parameter)
+            .collect(),
+        free: vec![],
+    };
+    walker.walk(body);
+    walker
+        .free
+        .into_iter()
+        .map(|(_, name, expr)| (name, expr))
+        .collect()
+}
+
+struct FreeVariables<'me> {
+    ast: &'me Ast,
+    resolutions: &'me Resolutions,
+
+    /// Variables and parameters declared inside the closure so far. Names are
+    /// resolved in order, so a use of one of them always follows its declaration.
+    declared: Vec<Definition>,
+
+    free: Vec<(Definition, Word, Expr)>,
+}
+
+impl FreeVariables<'_> {
+    fn walk(&mut self, expr: Expr) {
+        match &self.ast.tables[expr] {
+            ExprData::Id(name) => {
+                let definition = match self.resolutions.definition(expr) {
+                    Some(definition @ (Definition::Local(_) | Definition::Parameter(_))) => {
+                        definition
+                    }
+                    _ => return,
+                };
+                if !self.declared.contains(&definition)
+                    && !self.free.iter().any(|&(free, ..)| free == definition)
+                {
+                    self.free.push((definition, *name, expr));
+                }
+            }
+
+            ExprData::Var(_, _, initializer) => {
+                self.walk(*initializer);
+                self.declared.push(Definition::Local(expr));
+            }
+
+            ExprData::Block(block) => {
+                for &expr in &self.ast.tables[*block].exprs {
+                    self.walk(expr);
+                }
+            }
+
+            ExprData::Closure(_, parameters, body) => {
+                // Captures of a nested closure are captured by this one too.
+                self.declared
+                    .extend(parameters.iter().copied().map(Definition::Parameter));
+                self.walk(*body);
+            }
+
+            ExprData::StringLiteral(_)
+            | ExprData::NumberLiteral(_)
+            | ExprData::Continue
+            | ExprData::Error => {}
+
+            ExprData::Dot(expr, _)
+            | ExprData::Await(expr)
+            | ExprData::Share(expr)
+            | ExprData::Lease(expr)
+            | ExprData::Give(expr)
+            | ExprData::Parenthesized(expr)
+            | ExprData::Loop(expr)
+            | ExprData::Atomic(expr)
+            | ExprData::Unary(_, expr) => self.walk(*expr),
+
+            ExprData::Break(expr) | ExprData::Return(expr) => {
+                if let Some(expr) = *expr {
+                    self.walk(expr);
+                }
+            }
+
+            ExprData::Concatenate(exprs) | ExprData::Tuple(exprs) | ExprData::List(exprs) => {
+                for &expr in exprs {
+                    self.walk(expr);
+                }
+            }
+
+            ExprData::Call(callee, arguments) => {
+                self.walk(*callee);
+                for &argument in arguments {
+                    self.walk(self.ast.tables[argument].expr);
+                }
+            }
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                self.walk(*condition);
+                self.walk(*then_expr);
+                if let Some(else_expr) = *else_expr {
+                    self.walk(else_expr);
+                }
+            }
+
+            ExprData::While(lhs, rhs)
+            | ExprData::Index(lhs, rhs)
+            | ExprData::Op(lhs, _, rhs)
+            | ExprData::OpEq(lhs, _, rhs)
+            | ExprData::Assign(lhs, rhs) => {
+                self.walk(*lhs);
+                self.walk(*rhs);
+            }
+        }
+    }
+}
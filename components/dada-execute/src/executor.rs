@@ -0,0 +1,87 @@
+//! A deterministic, single-threaded executor for interpreter tasks.
+//!
+//! Each task runs until it suspends at an `.await`, at which point the
+//! next task in line gets to run. Tasks are resumed in round-robin order,
+//! so a given program always interleaves its tasks in the same way.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use crate::heap::Value;
+
+pub(crate) type BoxFuture<'me, T> = Pin<Box<dyn Future<Output = T> + 'me>>;
+
+pub(crate) type TaskFuture<'me> = BoxFuture<'me, eyre::Result<Value>>;
+
+#[derive(Default)]
+pub(crate) struct Executor<'me> {
+    tasks: VecDeque<TaskFuture<'me>>,
+}
+
+impl<'me> Executor<'me> {
+    pub(crate) fn spawn(&mut self, task: TaskFuture<'me>) {
+        self.tasks.push_back(task);
+    }
+
+    /// Runs tasks until all of them have completed or one of them fails.
+    /// After each step, `new_tasks` is invoked to collect the tasks that
+    /// were spawned during that step; they run before the task that
+    /// spawned them is resumed.
+    pub(crate) fn run(
+        &mut self,
+        mut new_tasks: impl FnMut() -> Vec<TaskFuture<'me>>,
+    ) -> eyre::Result<()> {
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        while let Some(mut task) = self.tasks.pop_front() {
+            let poll = task.as_mut().poll(&mut context);
+            self.tasks.extend(new_tasks());
+            match poll {
+                Poll::Ready(result) => {
+                    result?;
+                }
+                Poll::Pending => self.tasks.push_back(task),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Suspends the current task once, giving the other tasks a chance to run.
+pub(crate) fn yield_now() -> impl Future<Output = ()> {
+    YieldNow { yielded: false }
+}
+
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _context: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// Tasks never wait on anything external: a pending task is simply polled
+/// again on its next turn. Therefore wakeups can be ignored.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    // Safety: the vtable functions ignore the data pointer entirely.
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
@@ -0,0 +1,189 @@
+use dada_collections::{IndexMap, IndexVec, Map};
+use dada_id::id;
+use dada_ir::{
+    class::Class,
+    code::{CaptureMode, Expr},
+    func::{Function, Parameter},
+    intrinsic::Intrinsic,
+    span::Span,
+    word::Word,
+};
+
+use crate::permission::{
+    Access, ExpiryReason, Joint, Leased, Permission, PermissionData, PermissionState,
+};
+
+/// Every object created while interpreting lives in the heap
+/// and is referenced by an [`Object`] id. The heap also stores
+/// the [`Permission`]s that references carry.
+#[derive(Default)]
+pub(crate) struct Heap {
+    objects: IndexVec<Object, ObjectData>,
+    permissions: IndexVec<Permission, PermissionData>,
+
+    /// Number of committed transactions that have written to each atomic
+    /// field; used to detect conflicting commits.
+    atomic_versions: Map<(Object, Word), u64>,
+}
+
+id!(pub(crate) struct Object);
+
+/// The result of evaluating an expression.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum Value {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Reference(Reference),
+}
+
+/// A reference to an object in the heap, along with the permission
+/// that the holder has to use it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) struct Reference {
+    pub(crate) permission: Permission,
+    pub(crate) object: Object,
+}
+
+pub(crate) enum ObjectData {
+    String(String),
+    Instance(Instance),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Class(Class),
+    Function(Function),
+    Intrinsic(Intrinsic),
+    Thunk(Thunk),
+    Closure(Closure),
+}
+
+/// An instance of a class, created by calling the class's constructor.
+pub(crate) struct Instance {
+    pub(crate) class: Class,
+    pub(crate) fields: IndexMap<Word, Value>,
+}
+
+/// The result of evaluating a closure expression: its code along with
+/// the variables that it captured from the enclosing function.
+pub(crate) struct Closure {
+    /// The function in whose body the closure appears.
+    pub(crate) function: Function,
+    pub(crate) mode: CaptureMode,
+    pub(crate) parameters: Vec<Parameter>,
+    pub(crate) body: Expr,
+    pub(crate) captures: IndexMap<Word, Value>,
+}
+
+/// "Code waiting to run": the result of calling an `async fn`
+/// or an async intrinsic like `print`. Nothing happens until the
+/// thunk is awaited.
+#[derive(Clone)]
+pub(crate) enum Thunk {
+    Function(Function, Vec<(Word, Value)>),
+    Intrinsic(Intrinsic, Vec<(Word, Value)>),
+}
+
+impl Heap {
+    /// Allocates a new object; the caller becomes its unique owner.
+    pub(crate) fn new_object(&mut self, data: ObjectData) -> Value {
+        let object = Object::from(self.objects.len());
+        self.objects.push(data);
+        let permission = self.new_permission(PermissionData::new(Joint::No, Leased::No, None));
+        Value::Reference(Reference { permission, object })
+    }
+
+    pub(crate) fn data(&self, object: Object) -> &ObjectData {
+        &self.objects[object]
+    }
+
+    pub(crate) fn data_mut(&mut self, object: Object) -> &mut ObjectData {
+        &mut self.objects[object]
+    }
+
+    pub(crate) fn permission(&self, permission: Permission) -> &PermissionData {
+        &self.permissions[permission]
+    }
+
+    fn new_permission(&mut self, data: PermissionData) -> Permission {
+        let permission = Permission::from(self.permissions.len());
+        self.permissions.push(data);
+        permission
+    }
+
+    /// Creates a permission that is already expired; used to mark a place
+    /// whose value was given away.
+    pub(crate) fn expired_permission(&mut self, reason: ExpiryReason, span: Span) -> Permission {
+        let mut data = PermissionData::new(Joint::No, Leased::No, None);
+        data.state = PermissionState::Expired(reason, span);
+        self.new_permission(data)
+    }
+
+    /// Records an access through `permission` at `span`, cancelling any leases
+    /// whose terms it violates: reads cancel unique leases, writes cancel all leases.
+    pub(crate) fn access(&mut self, permission: Permission, access: Access, span: Span) {
+        let tenants = std::mem::take(&mut self.permissions[permission].tenants);
+        let mut remaining = vec![];
+        for tenant in tenants {
+            if access == Access::Write || self.permissions[tenant].joint == Joint::No {
+                self.expire(tenant, ExpiryReason::Cancelled, span);
+            } else {
+                remaining.push(tenant);
+            }
+        }
+        self.permissions[permission].tenants = remaining;
+    }
+
+    /// Expires `permission` along with everything leased from it.
+    pub(crate) fn expire(&mut self, permission: Permission, reason: ExpiryReason, span: Span) {
+        let data = &mut self.permissions[permission];
+        if !data.is_valid() {
+            return;
+        }
+        data.state = PermissionState::Expired(reason, span);
+        let tenants = std::mem::take(&mut data.tenants);
+        for tenant in tenants {
+            self.expire(tenant, ExpiryReason::Cancelled, span);
+        }
+    }
+
+    /// Creates a unique lease from `lessor`. A unique lease requires
+    /// exclusive access, so any existing leases are cancelled first.
+    pub(crate) fn lease(&mut self, lessor: Permission, span: Span) -> Permission {
+        self.access(lessor, Access::Write, span);
+        self.new_tenant(lessor, Joint::No)
+    }
+
+    /// Creates a shared lease from `lessor`. Existing shared leases remain valid.
+    pub(crate) fn shared_lease(&mut self, lessor: Permission, span: Span) -> Permission {
+        self.access(lessor, Access::Read, span);
+        self.new_tenant(lessor, Joint::Yes)
+    }
+
+    fn new_tenant(&mut self, lessor: Permission, joint: Joint) -> Permission {
+        let tenant = self.new_permission(PermissionData::new(joint, Leased::Yes, Some(lessor)));
+        self.permissions[lessor].tenants.push(tenant);
+        tenant
+    }
+
+    /// Converts a unique permission into a joint one in place: `my` becomes
+    /// `our` and `leased(p)` becomes `shared(p)`. Unique leases taken from
+    /// `permission` are cancelled, as they cannot coexist with sharing.
+    pub(crate) fn share(&mut self, permission: Permission, span: Span) {
+        self.access(permission, Access::Read, span);
+        self.permissions[permission].joint = Joint::Yes;
+    }
+
+    /// Current version of the atomic field `field` of `object`.
+    pub(crate) fn atomic_version(&self, object: Object, field: Word) -> u64 {
+        self.atomic_versions
+            .get(&(object, field))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Records that a transaction has committed a write to `field` of `object`.
+    pub(crate) fn bump_atomic_version(&mut self, object: Object, field: Word) {
+        *self.atomic_versions.entry((object, field)).or_insert(0) += 1;
+    }
+}
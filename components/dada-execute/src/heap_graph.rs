@@ -0,0 +1,677 @@
+//! Snapshots of the heap as seen from a stack frame, rendered either as the
+//! box diagrams used throughout the book or as Graphviz `dot` input.
+//!
+//! ```text
+//! ┌───┐
+//! │   │                  ┌───────┐
+//! │ p ├─my──────────────►│ Point │
+//! │   │                  │ ───── │
+//! │ q ├─leased(p)───────►│ x: 22 │
+//! │   │                  │ y: 44 │
+//! └───┘                  └───────┘
+//! ```
+
+use dada_collections::{IndexMap, IndexSet, Map};
+use dada_ir::word::Word;
+
+use crate::{
+    heap::{Heap, Object, ObjectData, Value},
+    permission::{Joint, Leased, Permission},
+};
+
+/// The variables of a stack frame along with every object reachable from them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeapGraph {
+    variables: Vec<Slot>,
+
+    /// Indexed by the `target` of [`SlotValue::Edge`].
+    objects: Vec<Node>,
+}
+
+/// A variable or a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Slot {
+    name: String,
+    value: SlotValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SlotValue {
+    /// A reference to a class instance, labelled with its permission (`my`, `leased(p)`, ...).
+    Edge { permission: String, target: usize },
+
+    /// Anything that is displayed inline, such as a string.
+    Data(String),
+
+    /// A reference whose permission has expired.
+    Expired,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node {
+    class_name: String,
+    fields: Vec<Slot>,
+}
+
+/// A variable or field, described for display in a debugger.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+
+    /// The value along with the permission to it, such as `my Point`,
+    /// `leased(p) Point`, or `"hello"`.
+    pub value: String,
+
+    /// The object referenced, if it has fields; see [`HeapGraph::fields`].
+    pub object: Option<usize>,
+}
+
+/// Which format to render a [`HeapGraph`] in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeapGraphFormat {
+    /// Box-drawing diagrams, as in the book.
+    Box,
+
+    /// Graphviz `dot` input.
+    Dot,
+}
+
+impl std::str::FromStr for HeapGraphFormat {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "box" => Ok(HeapGraphFormat::Box),
+            "dot" => Ok(HeapGraphFormat::Dot),
+            _ => eyre::bail!("unknown heap graph format `{s}` (expected `box` or `dot`)"),
+        }
+    }
+}
+
+impl HeapGraph {
+    /// Builds the graph reachable from `variables`, which are listed in declaration order.
+    pub(crate) fn new(db: &dyn crate::Db, heap: &Heap, variables: &[(Word, Value)]) -> Self {
+        let mut builder = Builder {
+            db,
+            heap,
+            objects: IndexSet::default(),
+            holders: Map::default(),
+        };
+
+        // Visit everything breadth-first, so that each permission is attributed
+        // to the shortest path that holds it (e.g. `p` rather than `q.next`).
+        let mut queue: Vec<(String, Value)> = variables
+            .iter()
+            .map(|&(name, value)| (name.as_str(db).to_string(), value))
+            .collect();
+        let mut index = 0;
+        while index < queue.len() {
+            let (path, value) = queue[index].clone();
+            index += 1;
+            if let Some(fields) = builder.visit(&path, value) {
+                queue.extend(fields);
+            }
+        }
+
+        let variables = variables
+            .iter()
+            .map(|&(name, value)| Slot {
+                name: name.as_str(db).to_string(),
+                value: builder.slot_value(value),
+            })
+            .collect();
+        let objects = builder
+            .objects
+            .iter()
+            .map(|&object| builder.node(object))
+            .collect();
+        HeapGraph { variables, objects }
+    }
+
+    /// Each variable in declaration order.
+    pub fn locals(&self) -> Vec<Entry> {
+        self.entries(&self.variables)
+    }
+
+    /// The fields of the object that [`Entry::object`] refers to.
+    pub fn fields(&self, object: usize) -> Vec<Entry> {
+        self.entries(&self.objects[object].fields)
+    }
+
+    fn entries(&self, slots: &[Slot]) -> Vec<Entry> {
+        slots
+            .iter()
+            .map(|slot| match &slot.value {
+                SlotValue::Edge { permission, target } => Entry {
+                    name: slot.name.clone(),
+                    value: format!("{permission} {}", self.objects[*target].class_name),
+                    object: Some(*target),
+                },
+                SlotValue::Data(data) => Entry {
+                    name: slot.name.clone(),
+                    value: data.clone(),
+                    object: None,
+                },
+                SlotValue::Expired => Entry {
+                    name: slot.name.clone(),
+                    value: "(expired)".to_string(),
+                    object: None,
+                },
+            })
+            .collect()
+    }
+
+    pub fn render(&self, format: HeapGraphFormat) -> String {
+        match format {
+            HeapGraphFormat::Box => self.to_box_diagram(),
+            HeapGraphFormat::Dot => self.to_graphviz(),
+        }
+    }
+
+    /// Renders the graph as Graphviz `dot` input.
+    pub fn to_graphviz(&self) -> String {
+        let mut lines = vec![
+            "digraph heap {".to_string(),
+            "  rankdir = LR;".to_string(),
+            "  node [shape = record];".to_string(),
+        ];
+
+        if !self.variables.is_empty() {
+            let ports: Vec<String> = self
+                .variables
+                .iter()
+                .enumerate()
+                .map(|(i, slot)| format!("<v{i}> {}", dot_escape(&slot.dot_label())))
+                .collect();
+            lines.push(format!("  variables [label = \"{}\"];", ports.join("|")));
+        }
+
+        for (n, node) in self.objects.iter().enumerate() {
+            let mut ports = vec![format!("<title> {}", dot_escape(&node.class_name))];
+            ports.extend(
+                node.fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, slot)| format!("<f{i}> {}", dot_escape(&slot.dot_label()))),
+            );
+            lines.push(format!("  n{n} [label = \"{}\"];", ports.join("|")));
+        }
+
+        let edges = |source: String, slots: &[Slot], port: &str| -> Vec<String> {
+            slots
+                .iter()
+                .enumerate()
+                .filter_map(|(i, slot)| match &slot.value {
+                    SlotValue::Edge { permission, target } => Some(format!(
+                        "  {source}:{port}{i} -> n{target}:title [label = \"{}\"];",
+                        dot_escape(permission)
+                    )),
+                    _ => None,
+                })
+                .collect()
+        };
+        lines.extend(edges("variables".to_string(), &self.variables, "v"));
+        for (n, node) in self.objects.iter().enumerate() {
+            lines.extend(edges(format!("n{n}"), &node.fields, "f"));
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// Renders the graph as a box diagram. Variables appear in a box on the
+    /// left, and each object is placed one column to the right of whatever
+    /// first refers to it, so that references are horizontal arrows.
+    /// References that cannot be drawn that way (e.g., to an object in an
+    /// earlier column) are written out as text instead.
+    pub fn to_box_diagram(&self) -> String {
+        if self.variables.is_empty() {
+            return String::new();
+        }
+
+        // Assign each object to a column: one past the column of the
+        // first thing (in breadth-first order) that refers to it.
+        let mut column_of = vec![usize::MAX; self.objects.len()];
+        let mut columns: Vec<Vec<usize>> = vec![vec![]];
+        let mut frontier: Vec<&[Slot]> = vec![&self.variables];
+        while !frontier.is_empty() {
+            let column = columns.len();
+            let mut nodes = vec![];
+            for slots in frontier {
+                for slot in slots {
+                    if let SlotValue::Edge { target, .. } = slot.value {
+                        if column_of[target] == usize::MAX {
+                            column_of[target] = column;
+                            nodes.push(target);
+                        }
+                    }
+                }
+            }
+            frontier = nodes.iter().map(|&n| &self.objects[n].fields[..]).collect();
+            if !nodes.is_empty() {
+                columns.push(nodes);
+            }
+        }
+
+        // Order the variables so that those referring to the same object are
+        // adjacent; that way each object's box can span all of its arrows.
+        let mut variables: Vec<&Slot> = self.variables.iter().collect();
+        variables.sort_by_key(|slot| {
+            match slot.value {
+                SlotValue::Edge { target, .. } => columns[1].iter().position(|&n| n == target),
+                _ => None,
+            }
+            .unwrap_or(usize::MAX)
+        });
+
+        // Arrows (and inline text) leaving each column, and the boxes in each column.
+        let mut arrows: Vec<Vec<Arrow>> = vec![vec![]; columns.len()];
+        let mut boxes: Vec<Vec<DrawnBox>> = vec![vec![]; columns.len()];
+
+        // Column 0: the variables, with a blank line between each of them.
+        let mut lines = vec![];
+        for (i, slot) in variables.iter().enumerate() {
+            let row = 2 + 2 * i;
+            lines.push((row, slot.name.clone()));
+            if let Some(arrow) = slot.arrow(row, 0, &column_of) {
+                arrows[0].push(arrow);
+            } else if let Some(text) = slot.box_text(self, 0, &column_of) {
+                arrows[0].push(Arrow {
+                    row,
+                    label: text,
+                    target: None,
+                });
+            }
+        }
+        boxes[0].push(DrawnBox {
+            top: 0,
+            bottom: 2 + 2 * variables.len(),
+            width: lines
+                .iter()
+                .map(|(_, l)| l.chars().count())
+                .max()
+                .unwrap_or(0),
+            lines,
+        });
+
+        // Remaining columns: objects, placed so that their box spans the
+        // rows of the arrows that point at them, where possible.
+        for column in 1..columns.len() {
+            let incoming = arrows[column - 1].clone();
+            let sources_of = |n: usize| -> Vec<usize> {
+                incoming
+                    .iter()
+                    .filter(|a| a.target == Some(n))
+                    .map(|a| a.row)
+                    .collect()
+            };
+            let mut nodes = columns[column].clone();
+            nodes.sort_by_key(|&n| sources_of(n).into_iter().min());
+
+            let mut next_free_row = 0;
+            for n in nodes {
+                let node = &self.objects[n];
+                let sources = sources_of(n);
+                let top = sources
+                    .iter()
+                    .min()
+                    .map(|&row| row.saturating_sub(1))
+                    .unwrap_or(0)
+                    .max(next_free_row);
+                let mut lines = vec![
+                    (top + 1, node.class_name.clone()),
+                    (top + 2, "─".repeat(node.class_name.chars().count())),
+                ];
+                for (i, slot) in node.fields.iter().enumerate() {
+                    let row = top + 3 + i;
+                    match slot.box_text(self, column, &column_of) {
+                        Some(text) => lines.push((row, format!("{}: {}", slot.name, text))),
+                        None => lines.push((row, slot.name.clone())),
+                    }
+                    if let Some(arrow) = slot.arrow(row, column, &column_of) {
+                        arrows[column].push(arrow);
+                    }
+                }
+                let last_content =
+                    (top + 2 + node.fields.len()).max(sources.iter().copied().max().unwrap_or(0));
+                let width = lines
+                    .iter()
+                    .map(|(_, l)| l.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                boxes[column].push(DrawnBox {
+                    top,
+                    bottom: last_content + 1,
+                    width,
+                    lines,
+                });
+                next_free_row = last_content + 2;
+            }
+        }
+
+        // Horizontal positions: each column of boxes is followed by a gap
+        // wide enough for the labels of the arrows leaving it.
+        let mut x = 0;
+        let mut column_x = vec![];
+        for column in 0..columns.len() {
+            column_x.push(x);
+            let box_width = boxes[column].iter().map(|b| b.width + 4).max().unwrap_or(0);
+            let gap = arrows[column]
+                .iter()
+                .map(|a| a.label.chars().count() + 4)
+                .max()
+                .unwrap_or(0)
+                .max(if column + 1 < columns.len() {
+                    MIN_ARROW_WIDTH
+                } else {
+                    0
+                });
+            x += box_width + gap;
+        }
+        column_x.push(x);
+
+        let height = boxes
+            .iter()
+            .flatten()
+            .map(|b| b.bottom + 1)
+            .max()
+            .unwrap_or(0);
+        let mut canvas = Canvas::new(height, x);
+        for column in 0..columns.len() {
+            for b in &boxes[column] {
+                b.draw(&mut canvas, column_x[column]);
+            }
+
+            for arrow in &arrows[column] {
+                // Arrows leave from the right edge of their own box, which may
+                // be narrower than the widest box in the column.
+                let b = boxes[column]
+                    .iter()
+                    .find(|b| b.top < arrow.row && arrow.row < b.bottom)
+                    .unwrap();
+                let from = column_x[column] + b.width + 4;
+                match arrow.target {
+                    Some(_) => {
+                        canvas.put(arrow.row, from - 1, '├');
+                        let mut text = format!("─{}", arrow.label);
+                        while text.chars().count() + 1 < column_x[column + 1] - from {
+                            text.push('─');
+                        }
+                        text.push('►');
+                        canvas.text(arrow.row, from, &text);
+                    }
+                    None => canvas.text(arrow.row, from, &format!(" {}", arrow.label)),
+                }
+            }
+        }
+
+        canvas.finish()
+    }
+}
+
+/// The minimum width of the space between two columns of boxes.
+const MIN_ARROW_WIDTH: usize = 18;
+
+#[derive(Clone, Debug)]
+struct Arrow {
+    row: usize,
+    label: String,
+
+    /// The object being pointed at, or `None` for text written next to the box.
+    target: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct DrawnBox {
+    top: usize,
+    bottom: usize,
+
+    /// Width of the widest line of content.
+    width: usize,
+
+    lines: Vec<(usize, String)>,
+}
+
+impl DrawnBox {
+    fn draw(&self, canvas: &mut Canvas, x: usize) {
+        let inner = "─".repeat(self.width + 2);
+        canvas.text(self.top, x, &format!("┌{inner}┐"));
+        for row in self.top + 1..self.bottom {
+            canvas.text(row, x, &format!("│{}│", " ".repeat(self.width + 2)));
+        }
+        for (row, line) in &self.lines {
+            canvas.text(*row, x + 2, line);
+        }
+        canvas.text(self.bottom, x, &format!("└{inner}┘"));
+    }
+}
+
+struct Canvas {
+    rows: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    fn new(height: usize, width: usize) -> Self {
+        Canvas {
+            rows: vec![vec![' '; width]; height],
+        }
+    }
+
+    fn put(&mut self, row: usize, column: usize, ch: char) {
+        let line = &mut self.rows[row];
+        if line.len() <= column {
+            line.resize(column + 1, ' ');
+        }
+        line[column] = ch;
+    }
+
+    fn text(&mut self, row: usize, column: usize, text: &str) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(row, column + i, ch);
+        }
+    }
+
+    fn finish(self) -> String {
+        self.rows
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Slot {
+    /// The arrow that leaves this slot, if it refers to an object in the next column.
+    fn arrow(&self, row: usize, column: usize, column_of: &[usize]) -> Option<Arrow> {
+        match &self.value {
+            SlotValue::Edge { permission, target } if column_of[*target] == column + 1 => {
+                Some(Arrow {
+                    row,
+                    label: permission.clone(),
+                    target: Some(*target),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The text to show for this slot inside its box, if it is not drawn as an arrow.
+    fn box_text(&self, graph: &HeapGraph, column: usize, column_of: &[usize]) -> Option<String> {
+        match &self.value {
+            SlotValue::Edge { permission, target } => {
+                if column_of[*target] == column + 1 {
+                    None
+                } else {
+                    Some(format!(
+                        "{permission} {}",
+                        graph.objects[*target].class_name
+                    ))
+                }
+            }
+            SlotValue::Data(data) => Some(data.clone()),
+            SlotValue::Expired => None,
+        }
+    }
+
+    fn dot_label(&self) -> String {
+        match &self.value {
+            SlotValue::Edge { .. } | SlotValue::Expired => self.name.clone(),
+            SlotValue::Data(data) => format!("{}: {}", self.name, data),
+        }
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if matches!(ch, '{' | '}' | '|' | '<' | '>' | '"' | '\\' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+struct Builder<'a> {
+    db: &'a dyn crate::Db,
+    heap: &'a Heap,
+
+    /// Each object drawn as a node (class instances, closures, lists and tuples)
+    /// reached so far; the index is its node number.
+    objects: IndexSet<Object>,
+
+    /// For each permission, the path (`p`, `p.next`) of the first slot found holding it.
+    holders: Map<Permission, String>,
+}
+
+impl Builder<'_> {
+    /// Records that `path` holds `value`; if `value` refers to an object drawn
+    /// as a node that was not seen before, returns its slots so that they are
+    /// visited in turn.
+    fn visit(&mut self, path: &str, value: Value) -> Option<Vec<(String, Value)>> {
+        let reference = match value {
+            Value::Reference(reference) => reference,
+            _ => return None,
+        };
+        self.holders
+            .entry(reference.permission)
+            .or_insert_with(|| path.to_string());
+
+        if !self.heap.permission(reference.permission).is_valid() {
+            return None;
+        }
+
+        let (_, slots) = self.contents(reference.object)?;
+        if !self.objects.insert(reference.object) {
+            return None;
+        }
+        Some(
+            slots
+                .into_iter()
+                .map(|(name, value)| {
+                    if name.starts_with('[') {
+                        (format!("{path}{name}"), value)
+                    } else {
+                        (format!("{path}.{name}"), value)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// If `object` is drawn as a node, returns its name along with its slots: the
+    /// fields of an instance, the captures of a closure, or the elements of a list
+    /// or tuple (named `[0]`, `[1]`, ...).
+    fn contents(&self, object: Object) -> Option<(String, Vec<(String, Value)>)> {
+        let named = |fields: &IndexMap<Word, Value>| {
+            fields
+                .iter()
+                .map(|(name, &value)| (name.as_str(self.db).to_string(), value))
+                .collect()
+        };
+        let indexed = |elements: &[Value]| {
+            elements
+                .iter()
+                .enumerate()
+                .map(|(index, &value)| (format!("[{index}]"), value))
+                .collect()
+        };
+        match self.heap.data(object) {
+            ObjectData::Instance(instance) => Some((
+                instance.class.name(self.db).as_str(self.db).to_string(),
+                named(&instance.fields),
+            )),
+            ObjectData::Closure(closure) => {
+                Some(("<closure>".to_string(), named(&closure.captures)))
+            }
+            ObjectData::List(elements) => Some(("<list>".to_string(), indexed(elements))),
+            ObjectData::Tuple(elements) => Some(("<tuple>".to_string(), indexed(elements))),
+            _ => None,
+        }
+    }
+
+    fn node(&self, object: Object) -> Node {
+        let (class_name, slots) = self
+            .contents(object)
+            .expect("only objects with contents are nodes");
+        Node {
+            class_name,
+            fields: slots
+                .into_iter()
+                .map(|(name, value)| Slot {
+                    name,
+                    value: self.slot_value(value),
+                })
+                .collect(),
+        }
+    }
+
+    fn slot_value(&self, value: Value) -> SlotValue {
+        let reference = match value {
+            Value::Unit => return SlotValue::Data("()".to_string()),
+            Value::Bool(b) => return SlotValue::Data(b.to_string()),
+            Value::Int(i) => return SlotValue::Data(i.to_string()),
+            Value::Float(f) => return SlotValue::Data(format!("{f:?}")),
+            Value::Reference(reference) => reference,
+        };
+
+        let permission = self.heap.permission(reference.permission);
+        if !permission.is_valid() {
+            return SlotValue::Expired;
+        }
+
+        match self.heap.data(reference.object) {
+            ObjectData::Instance(_)
+            | ObjectData::Closure(_)
+            | ObjectData::List(_)
+            | ObjectData::Tuple(_) => {
+                let lessor = || {
+                    permission
+                        .lessor
+                        .and_then(|lessor| self.holders.get(&lessor))
+                        .cloned()
+                        .unwrap_or_else(|| "?".to_string())
+                };
+                let label = match (permission.joint, permission.leased) {
+                    (Joint::No, Leased::No) => "my".to_string(),
+                    (Joint::Yes, Leased::No) => "our".to_string(),
+                    (Joint::No, Leased::Yes) => format!("leased({})", lessor()),
+                    (Joint::Yes, Leased::Yes) => format!("shared({})", lessor()),
+                };
+                SlotValue::Edge {
+                    permission: label,
+                    target: self.objects.get_index_of(&reference.object).unwrap(),
+                }
+            }
+            ObjectData::String(s) => SlotValue::Data(format!("{s:?}")),
+            ObjectData::Class(class) => {
+                SlotValue::Data(class.name(self.db).as_str(self.db).to_string())
+            }
+            ObjectData::Function(function) => {
+                SlotValue::Data(function.name(self.db).as_str(self.db).to_string())
+            }
+            ObjectData::Intrinsic(intrinsic) => SlotValue::Data(intrinsic.str().to_string()),
+            ObjectData::Thunk(_) => SlotValue::Data("<thunk>".to_string()),
+        }
+    }
+}
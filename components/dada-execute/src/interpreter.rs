@@ -0,0 +1,1699 @@
+use std::{
+    cell::{Cell, Ref, RefCell, RefMut},
+    cmp::Ordering,
+    ops::ControlFlow,
+};
+
+use dada_collections::{IndexMap, Map};
+use dada_ir::{
+    arguments::match_arguments,
+    class::Class,
+    code::{Ast, Block, CaptureMode, Expr, ExprData, Spans},
+    definition::{Definition, Resolutions},
+    diagnostic::{Diagnostic, Label},
+    func::{Effect, Function, Parameter},
+    intrinsic::Intrinsic,
+    item::Item,
+    number::Number,
+    op::Op,
+    span::Span,
+    storage_mode::StorageMode,
+    word::Word,
+};
+use dada_parse::prelude::*;
+use std::io::Write;
+
+use crate::{
+    executor::{yield_now, BoxFuture, Executor, TaskFuture},
+    heap::{Closure, Heap, Instance, Object, ObjectData, Reference, Thunk, Value},
+    heap_graph::HeapGraph,
+    observer::{Inspect, Observer, StackFrame, Stop},
+    permission::{Access, ExpiryReason, Joint, Leased, PermissionData, PermissionState},
+};
+
+/// Executes the `main` function found in `filename`, writing anything
+/// that the program prints to `stdout`. Returns once `main` and every
+/// task that it spawned have completed.
+pub fn interpret(
+    db: &dyn crate::Db,
+    filename: Word,
+    stdout: &mut dyn std::io::Write,
+) -> eyre::Result<()> {
+    interpret_with_observer(db, filename, stdout, &mut ())
+}
+
+/// Like [`interpret`], but notifies `observer` as execution proceeds.
+pub fn interpret_with_observer(
+    db: &dyn crate::Db,
+    filename: Word,
+    stdout: &mut dyn std::io::Write,
+    observer: &mut dyn Observer,
+) -> eyre::Result<()> {
+    run(db, filename, "main", stdout, observer).map(|_| ())
+}
+
+/// Like [`interpret`], but starts by calling the function named `entry`
+/// (which must take no arguments) instead of `main`. Returns the value that
+/// `entry` returned, as `print` would display it, or `None` if it was `()`.
+pub fn interpret_entry(
+    db: &dyn crate::Db,
+    filename: Word,
+    entry: &str,
+    stdout: &mut dyn std::io::Write,
+) -> eyre::Result<Option<String>> {
+    run(db, filename, entry, stdout, &mut ())
+}
+
+fn run(
+    db: &dyn crate::Db,
+    filename: Word,
+    entry: &str,
+    stdout: &mut dyn std::io::Write,
+    observer: &mut dyn Observer,
+) -> eyre::Result<Option<String>> {
+    let main = dada_parse::parse_file(db, filename)
+        .iter()
+        .find_map(|&item| match item {
+            Item::Function(function) if function.name(db).as_str(db) == entry => Some(function),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "could not find a function named `{entry}` in `{}`",
+                filename.as_str(db)
+            )
+        })?;
+
+    let interpreter = Interpreter {
+        db,
+        filename,
+        stdout: RefCell::new(stdout),
+        observer: RefCell::new(observer),
+        heap: RefCell::default(),
+        spawned: RefCell::default(),
+        quit: Cell::new(false),
+        returned: RefCell::default(),
+    };
+
+    let mut executor = Executor::default();
+    executor.spawn(interpreter.run_entry(main));
+    let result = executor.run(|| {
+        interpreter
+            .spawned
+            .take()
+            .into_iter()
+            .map(|(thunk, span)| interpreter.run_thunk(thunk, span))
+            .collect()
+    });
+    if interpreter.quit.get() {
+        return Ok(None);
+    }
+    result?;
+    Ok(interpreter.returned.take())
+}
+
+/// State shared by all tasks.
+struct Interpreter<'me> {
+    db: &'me dyn crate::Db,
+
+    /// File containing the program being executed.
+    filename: Word,
+
+    stdout: RefCell<&'me mut dyn std::io::Write>,
+    observer: RefCell<&'me mut dyn Observer>,
+    heap: RefCell<Heap>,
+
+    /// Thunks passed to `spawn` that have not been started yet,
+    /// along with the span of the call to `spawn`.
+    spawned: RefCell<Vec<(Thunk, Span)>>,
+
+    /// Set once the observer asks to stop the program; the task that was
+    /// running then fails, which stops the executor.
+    quit: Cell<bool>,
+
+    /// What the entry function returned, as `print` would display it,
+    /// unless it returned `()`.
+    returned: RefCell<Option<String>>,
+}
+
+impl<'me> Interpreter<'me> {
+    /// Creates a new task that runs `thunk`; `span` is used to report
+    /// errors that are not attributable to any particular expression.
+    fn run_thunk(&'me self, thunk: Thunk, span: Span) -> TaskFuture<'me> {
+        Box::pin(async move { self.new_task().run_thunk(thunk, span).await })
+    }
+
+    /// Creates the task that calls `entry`, recording what it returns in `returned`.
+    fn run_entry(&'me self, entry: Function) -> TaskFuture<'me> {
+        Box::pin(async move {
+            let mut task = self.new_task();
+            let thunk = Thunk::Function(entry, vec![]);
+            let value = task.run_thunk(thunk, entry.name_span(self.db)).await?;
+            if value != Value::Unit {
+                *self.returned.borrow_mut() = Some(task.stringify(value));
+            }
+            Ok(value)
+        })
+    }
+
+    fn new_task(&'me self) -> Task<'me> {
+        Task {
+            db: self.db,
+            interpreter: self,
+            stack: vec![],
+            transaction: None,
+            jump: None,
+        }
+    }
+}
+
+/// The state of a single task: its call stack and current transaction.
+struct Task<'me> {
+    db: &'me dyn crate::Db,
+    interpreter: &'me Interpreter<'me>,
+    stack: Vec<Frame<'me>>,
+
+    /// The innermost `atomic` block being executed, if any.
+    transaction: Option<Transaction>,
+
+    /// Set by `break`, `continue` and `return`, which then return an error so
+    /// that `?` unwinds to the enclosing loop or function, which clears it.
+    jump: Option<Jump>,
+}
+
+enum Jump {
+    Break(Value),
+    Continue,
+    Return(Value),
+}
+
+/// The state for one active function (or closure) call.
+struct Frame<'me> {
+    /// The function being called; for a closure, the function containing it.
+    function: Function,
+    ast: &'me Ast,
+    spans: &'me Spans,
+
+    /// The definition that each name in the function's body refers to.
+    resolutions: &'me Resolutions,
+
+    /// Closures are never async, even inside of an `async fn`.
+    effect: Effect,
+
+    /// Local variables and parameters, keyed by their definition,
+    /// one map per enclosing block (innermost last), each in declaration order.
+    scopes: Vec<IndexMap<Definition, Local>>,
+
+    /// For a closure, the closure object, whose captured variables are
+    /// in scope (and stay stored in it) while the call runs.
+    closure: Option<Object>,
+
+    /// The statement being executed.
+    position: Span,
+
+    /// Number of expressions being evaluated; see [`Stop::nesting`].
+    nesting: usize,
+}
+
+/// Tracks the atomic fields used by an `atomic` block. Writes are buffered
+/// until the block completes and discarded if it fails. Tasks behave as if
+/// they ran in parallel: before committing, the task lets the other tasks
+/// run, and if one of them has meanwhile committed to a field that was
+/// read, the block is aborted and run again.
+struct Transaction {
+    /// The `atomic` block being executed.
+    span: Span,
+
+    /// Version of each atomic field when it was first read.
+    reads: Map<(Object, Word), u64>,
+
+    /// Values written to atomic fields, applied on commit.
+    writes: Map<(Object, Word), Value>,
+}
+
+struct Local {
+    name: Word,
+    mode: StorageMode,
+    value: Value,
+}
+
+/// A location that a value can be read from or written to.
+enum Place {
+    Local(Definition),
+
+    /// A variable captured by the given closure.
+    Capture(Object, Word),
+
+    Field {
+        owner: Reference,
+        field: Word,
+        path: Path,
+
+        /// True for a shared, atomic field, which is accessed through
+        /// the current transaction.
+        atomic: bool,
+    },
+
+    /// An element of a list or tuple.
+    Element {
+        owner: Reference,
+        index: usize,
+        path: Path,
+    },
+
+    /// The result of an expression that does not name a place, like `Point(..)`.
+    Temporary(Value),
+}
+
+/// Summarizes the permissions used to reach a field: anything reached through
+/// a shared permission is shared, and anything reached through a lease is leased.
+#[derive(Copy, Clone, Default)]
+struct Path {
+    joint: bool,
+    leased: bool,
+}
+
+impl Path {
+    fn through(self, permission: &PermissionData) -> Path {
+        Path {
+            joint: self.joint || permission.joint == Joint::Yes,
+            leased: self.leased || permission.leased == Leased::Yes,
+        }
+    }
+}
+
+impl Place {
+    fn path(&self) -> Path {
+        match self {
+            Place::Field { path, .. } | Place::Element { path, .. } => *path,
+            Place::Local(_) | Place::Capture(..) | Place::Temporary(_) => Path::default(),
+        }
+    }
+}
+
+/// Something that can be called.
+enum Callee {
+    Class(Class),
+    Function(Function),
+    Intrinsic(Intrinsic),
+    Closure(Object, Vec<Parameter>),
+}
+
+impl Callee {
+    fn name(&self, db: &dyn crate::Db) -> String {
+        match self {
+            Callee::Class(class) => class.name(db).as_str(db).to_string(),
+            Callee::Function(function) => function.name(db).as_str(db).to_string(),
+            Callee::Intrinsic(intrinsic) => intrinsic.str().to_string(),
+            Callee::Closure(..) => "closure".to_string(),
+        }
+    }
+
+    /// Names of the arguments expected by the callee, in declaration order.
+    fn parameters(&self, db: &dyn crate::Db) -> Vec<Word> {
+        match self {
+            Callee::Class(class) => class.fields(db).iter().map(|f| f.name(db)).collect(),
+            Callee::Function(function) => {
+                function.parameters(db).iter().map(|p| p.name(db)).collect()
+            }
+            Callee::Intrinsic(intrinsic) => intrinsic
+                .parameters()
+                .iter()
+                .map(|name| Word::from(db, name))
+                .collect(),
+            Callee::Closure(_, parameters) => parameters.iter().map(|p| p.name(db)).collect(),
+        }
+    }
+}
+
+impl<'me> Task<'me> {
+    fn frame(&self) -> &Frame<'me> {
+        self.stack.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame<'me> {
+        self.stack.last_mut().unwrap()
+    }
+
+    fn heap(&self) -> Ref<'me, Heap> {
+        self.interpreter.heap.borrow()
+    }
+
+    fn heap_mut(&self) -> RefMut<'me, Heap> {
+        self.interpreter.heap.borrow_mut()
+    }
+
+    fn span(&self, expr: Expr) -> Span {
+        self.frame().spans[expr]
+    }
+
+    /// Creates an error located at `expr`.
+    fn error(&self, expr: Expr, message: impl AsRef<str>) -> eyre::Report {
+        self.error_at(self.span(expr), message)
+    }
+
+    fn error_at(&self, span: Span, message: impl AsRef<str>) -> eyre::Report {
+        self.error_with_labels(span, message, vec![])
+    }
+
+    fn error_with_labels(
+        &self,
+        span: Span,
+        message: impl AsRef<str>,
+        labels: Vec<Label>,
+    ) -> eyre::Report {
+        let diagnostic = Diagnostic {
+            filename: self.interpreter.filename,
+            span,
+            message: message.as_ref().to_string(),
+            labels,
+        };
+        eyre::eyre!("{}", dada_lex::format_diagnostic(self.db, &diagnostic))
+    }
+
+    /// Runs the code that `thunk` is waiting to run.
+    async fn run_thunk(&mut self, thunk: Thunk, span: Span) -> eyre::Result<Value> {
+        match thunk {
+            Thunk::Function(function, arguments) => self.call_function(function, arguments).await,
+            Thunk::Intrinsic(intrinsic, arguments) => {
+                self.call_intrinsic(intrinsic, arguments, span)
+            }
+        }
+    }
+
+    /// Invokes `function` immediately (even if it is an `async fn`),
+    /// binding each argument, which must already be in declaration order,
+    /// to the corresponding parameter.
+    /// The body shares a scope with the parameters, so that the observer
+    /// sees all of the function's locals before they are dropped.
+    async fn call_function(
+        &mut self,
+        function: Function,
+        arguments: Vec<(Word, Value)>,
+    ) -> eyre::Result<Value> {
+        let ast = function.ast(self.db);
+        let spans = function.spans(self.db);
+        let parameters = function
+            .parameters(self.db)
+            .iter()
+            .zip(arguments)
+            .map(|(&parameter, (name, value))| {
+                let local = Local {
+                    name,
+                    mode: parameter.mode(self.db),
+                    value,
+                };
+                (Definition::Parameter(parameter), local)
+            })
+            .collect();
+        self.stack.push(Frame {
+            function,
+            ast,
+            spans,
+            resolutions: dada_resolve::resolve_names(self.db, function),
+            effect: function.effect(self.db),
+            scopes: vec![parameters],
+            closure: None,
+            position: function.name_span(self.db),
+            nesting: 0,
+        });
+        let span = function.name_span(self.db);
+        let result = self.eval_exprs(&ast.tables[ast.block].exprs).await;
+        let result = match (result, self.jump.take()) {
+            (Err(_), Some(Jump::Return(value))) => Ok(value),
+            (result, _) => result,
+        };
+        let exit = self.observe(span, |observer, stop| observer.function_exit(stop));
+        self.pop_scope(span);
+        self.stack.pop();
+        exit?;
+        result
+    }
+
+    /// Invokes the closure `object`. Its captured variables are in scope
+    /// alongside its parameters (and can be assigned unless they were shared),
+    /// but stay stored in the closure, so that calls made while this one is
+    /// running see them too; when the call ends, the parameters are dropped.
+    async fn call_closure(
+        &mut self,
+        object: Object,
+        arguments: Vec<(Word, Value)>,
+    ) -> eyre::Result<Value> {
+        let (function, body, declared) = match self.heap().data(object) {
+            ObjectData::Closure(closure) => {
+                (closure.function, closure.body, closure.parameters.clone())
+            }
+            _ => unreachable!("callee is not a closure"),
+        };
+        let parameters = declared
+            .into_iter()
+            .zip(arguments)
+            .map(|(parameter, (name, value))| {
+                let local = Local {
+                    name,
+                    mode: StorageMode::Shared,
+                    value,
+                };
+                (Definition::Parameter(parameter), local)
+            })
+            .collect();
+
+        let ast = function.ast(self.db);
+        let spans = function.spans(self.db);
+        let span = spans[body];
+        self.stack.push(Frame {
+            function,
+            ast,
+            spans,
+            resolutions: dada_resolve::resolve_names(self.db, function),
+            effect: Effect::None,
+            scopes: vec![parameters],
+            closure: Some(object),
+            position: span,
+            nesting: 0,
+        });
+        let result = self.eval_expr(body).await;
+        let result = match (result, self.jump.take()) {
+            (Err(_), Some(Jump::Return(value))) => Ok(value),
+            (result, _) => result,
+        };
+        self.pop_scope(span);
+        self.stack.pop();
+        result
+    }
+
+    /// Evaluates `block`; `span` is the span of the enclosing expression,
+    /// used to record where locals declared in the block are dropped.
+    async fn eval_block(&mut self, block: Block, span: Span) -> eyre::Result<Value> {
+        let ast = self.frame().ast;
+        self.frame_mut().scopes.push(IndexMap::default());
+        let result = self.eval_exprs(&ast.tables[block].exprs).await;
+        self.pop_scope(span);
+        result
+    }
+
+    /// Notifies the observer that execution has reached `span` in the current
+    /// frame. Fails if the observer asks to stop the program.
+    fn observe(
+        &self,
+        span: Span,
+        notify: impl FnOnce(&mut dyn Observer, &Stop<'_>) -> ControlFlow<()>,
+    ) -> eyre::Result<()> {
+        let frame = self.frame();
+        let stop = Stop::new(frame.function, span, self.stack.len(), frame.nesting, self);
+        match notify(&mut **self.interpreter.observer.borrow_mut(), &stop) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => {
+                self.interpreter.quit.set(true);
+                Err(eyre::eyre!("program stopped by the observer"))
+            }
+        }
+    }
+
+    /// Pops the innermost scope, dropping the values owned by its locals.
+    fn pop_scope(&mut self, span: Span) {
+        let scope = self.frame_mut().scopes.pop().unwrap();
+        for (_, local) in scope {
+            self.drop_value(local.value, span);
+        }
+    }
+
+    /// Given the result of running a loop body, returns the result of the whole
+    /// loop if it should stop: because of `break`, or because of an error or
+    /// `return` that should propagate further.
+    fn loop_exit(&mut self, result: eyre::Result<Value>) -> Option<eyre::Result<Value>> {
+        let error = match result {
+            Ok(_) => return None,
+            Err(error) => error,
+        };
+        match self.jump.take() {
+            Some(Jump::Break(value)) => Some(Ok(value)),
+            Some(Jump::Continue) => None,
+            jump => {
+                self.jump = jump;
+                Some(Err(error))
+            }
+        }
+    }
+
+    /// Evaluates each of `exprs` in turn, returning the value of the last one.
+    async fn eval_exprs(&mut self, exprs: &[Expr]) -> eyre::Result<Value> {
+        let mut value = Value::Unit;
+        for &expr in exprs {
+            let span = self.span(expr);
+            self.frame_mut().position = span;
+            self.observe(span, |observer, stop| observer.before_statement(stop))?;
+            value = self.eval_expr(expr).await?;
+        }
+        Ok(value)
+    }
+
+    /// Evaluates `expr`, after notifying the observer that it is about to be evaluated.
+    async fn eval_expr(&mut self, expr: Expr) -> eyre::Result<Value> {
+        self.frame_mut().nesting += 1;
+        let span = self.span(expr);
+        let result = match self.observe(span, |observer, stop| observer.before_expr(stop)) {
+            Ok(()) => self.eval_expr_unobserved(expr).await,
+            Err(error) => Err(error),
+        };
+        self.frame_mut().nesting -= 1;
+        result
+    }
+
+    fn eval_expr_unobserved(&mut self, expr: Expr) -> BoxFuture<'_, eyre::Result<Value>> {
+        Box::pin(async move {
+            let ast = self.frame().ast;
+            match &ast.tables[expr] {
+                ExprData::Id(_) | ExprData::Dot(..) | ExprData::Index(..) | ExprData::Give(_) => {
+                    self.give(expr).await
+                }
+
+                ExprData::StringLiteral(word) => {
+                    let string = word.as_str(self.db).to_string();
+                    Ok(self.heap_mut().new_object(ObjectData::String(string)))
+                }
+
+                ExprData::Concatenate(exprs) => {
+                    let mut string = String::new();
+                    for &expr in exprs {
+                        let value = match &ast.tables[expr] {
+                            ExprData::StringLiteral(word) => {
+                                string.push_str(word.as_str(self.db));
+                                continue;
+                            }
+                            // Interpolating a variable only reads it.
+                            ExprData::Id(_) | ExprData::Dot(..) | ExprData::Index(..) => {
+                                self.read_shared(expr).await?
+                            }
+                            _ => self.eval_expr(expr).await?,
+                        };
+                        string.push_str(&self.stringify(value));
+                    }
+                    Ok(self.heap_mut().new_object(ObjectData::String(string)))
+                }
+
+                ExprData::NumberLiteral(number) => Ok(match *number {
+                    Number::Integer(i) => Value::Int(i),
+                    Number::Float(f) => Value::Float(f.value()),
+                }),
+
+                ExprData::Await(thunk) => {
+                    if self.frame().effect != Effect::Async {
+                        return Err(
+                            self.error(expr, "await is only permitted inside of an async fn")
+                        );
+                    }
+                    if let Some(transaction) = &self.transaction {
+                        let label = Label {
+                            span: transaction.span,
+                            message: "atomic block started here".to_string(),
+                        };
+                        return Err(self.error_with_labels(
+                            self.span(expr),
+                            "await cannot be used inside of an atomic block",
+                            vec![label],
+                        ));
+                    }
+                    let thunk_value = self.eval_expr(*thunk).await?;
+                    self.await_thunk(thunk_value, expr).await
+                }
+
+                ExprData::Call(callee, named_exprs) => {
+                    let callee = self.eval_expr(*callee).await?;
+                    let mut arguments = Vec::with_capacity(named_exprs.len());
+                    for &named_expr in named_exprs {
+                        let named_expr = &ast.tables[named_expr];
+                        let value = self.eval_expr(named_expr.expr).await?;
+                        arguments.push((named_expr.name, value));
+                    }
+                    self.call(callee, arguments, expr).await
+                }
+
+                ExprData::Share(place) => self.share(*place).await,
+
+                ExprData::Lease(place) => self.lease(*place).await,
+
+                ExprData::Var(mode, name, initializer) => {
+                    let span = self.span(expr);
+                    let mut value = self.eval_expr(*initializer).await?;
+                    if let StorageMode::Shared = mode {
+                        value = self.share_value(value, span);
+                    }
+                    let scope = self.frame_mut().scopes.last_mut().unwrap();
+                    // A variable redeclared in the same block can no longer be named.
+                    let old_local = scope
+                        .iter()
+                        .position(|(_, local)| local.name == *name)
+                        .and_then(|index| scope.shift_remove_index(index));
+                    let local = Local {
+                        name: *name,
+                        mode: *mode,
+                        value,
+                    };
+                    scope.insert(Definition::Local(expr), local);
+                    if let Some((_, old_local)) = old_local {
+                        self.drop_value(old_local.value, span);
+                    }
+                    Ok(Value::Unit)
+                }
+
+                ExprData::Parenthesized(expr) => self.eval_expr(*expr).await,
+
+                ExprData::Tuple(exprs) if exprs.is_empty() => Ok(Value::Unit),
+
+                ExprData::Tuple(exprs) | ExprData::List(exprs) => {
+                    let mut values = Vec::with_capacity(exprs.len());
+                    for &element in exprs {
+                        values.push(self.eval_expr(element).await?);
+                    }
+                    let data = match &ast.tables[expr] {
+                        ExprData::Tuple(_) => ObjectData::Tuple(values),
+                        _ => ObjectData::List(values),
+                    };
+                    Ok(self.heap_mut().new_object(data))
+                }
+
+                ExprData::If(condition, then_expr, else_expr) => {
+                    let value = self.eval_expr(*condition).await?;
+                    if self.expect_bool(value, *condition)? {
+                        self.eval_expr(*then_expr).await
+                    } else if let Some(else_expr) = else_expr {
+                        self.eval_expr(*else_expr).await
+                    } else {
+                        Ok(Value::Unit)
+                    }
+                }
+
+                ExprData::Loop(body) => loop {
+                    let result = self.eval_expr(*body).await;
+                    if let Some(result) = self.loop_exit(result) {
+                        return result;
+                    }
+                },
+
+                ExprData::While(condition, body) => {
+                    loop {
+                        let value = self.eval_expr(*condition).await?;
+                        if !self.expect_bool(value, *condition)? {
+                            break;
+                        }
+                        let result = self.eval_expr(*body).await;
+                        if let Some(result) = self.loop_exit(result) {
+                            return result.map(|_| Value::Unit);
+                        }
+                    }
+                    Ok(Value::Unit)
+                }
+
+                ExprData::Break(value) => {
+                    let value = match value {
+                        Some(value) => self.eval_expr(*value).await?,
+                        None => Value::Unit,
+                    };
+                    self.jump = Some(Jump::Break(value));
+                    Err(self.error(expr, "`break` outside of a loop"))
+                }
+
+                ExprData::Continue => {
+                    self.jump = Some(Jump::Continue);
+                    Err(self.error(expr, "`continue` outside of a loop"))
+                }
+
+                ExprData::Return(value) => {
+                    let value = match value {
+                        Some(value) => self.eval_expr(*value).await?,
+                        None => Value::Unit,
+                    };
+                    self.jump = Some(Jump::Return(value));
+                    Err(self.error(expr, "`return` outside of a function"))
+                }
+
+                ExprData::Closure(mode, parameters, body) => {
+                    let function = self.frame().function;
+                    let mut captures = IndexMap::default();
+                    for (name, id_expr) in
+                        crate::captures::free_variables(self.db, function, parameters, *body)
+                    {
+                        let value = match mode {
+                            CaptureMode::Give => self.give(id_expr).await?,
+                            CaptureMode::Lease => self.lease(id_expr).await?,
+                            CaptureMode::Share => self.share(id_expr).await?,
+                        };
+                        captures.insert(name, value);
+                    }
+                    let closure = Closure {
+                        function,
+                        mode: *mode,
+                        parameters: parameters.clone(),
+                        body: *body,
+                        captures,
+                    };
+                    Ok(self.heap_mut().new_object(ObjectData::Closure(closure)))
+                }
+
+                ExprData::Block(block) => {
+                    let span = self.span(expr);
+                    self.eval_block(*block, span).await
+                }
+
+                ExprData::Atomic(body) => {
+                    if self.transaction.is_some() {
+                        // Nested atomic blocks join the enclosing transaction.
+                        return self.eval_expr(*body).await;
+                    }
+
+                    loop {
+                        self.transaction = Some(Transaction {
+                            span: self.span(expr),
+                            reads: Map::default(),
+                            writes: Map::default(),
+                        });
+                        let result = self.eval_expr(*body).await;
+                        let transaction = self.transaction.take().unwrap();
+                        if result.is_err() && self.jump.is_none() {
+                            return result;
+                        }
+                        // Give the other tasks the chance to commit first, as
+                        // they could if they were running on other threads.
+                        yield_now().await;
+                        // Leaving the block with `break` or `return` completes it too.
+                        if self.commit(transaction) {
+                            return result;
+                        }
+                        self.jump = None;
+                    }
+                }
+
+                ExprData::Unary(op, operand) => {
+                    let value = self.eval_expr(*operand).await?;
+                    match (op, value) {
+                        (Op::Minus, Value::Int(i)) => i
+                            .checked_neg()
+                            .map(Value::Int)
+                            .ok_or_else(|| self.error(expr, "integer overflow in `-`")),
+                        (Op::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+                        (Op::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                        _ => Err(self.error(expr, format!("cannot apply `{op}` to this value"))),
+                    }
+                }
+
+                ExprData::Op(lhs, op @ (Op::AndAnd | Op::OrOr), rhs) => {
+                    // Only evaluate `rhs` if `lhs` does not decide the result.
+                    let lhs_value = self.eval_expr(*lhs).await?;
+                    let lhs_value = self.expect_bool(lhs_value, *lhs)?;
+                    if lhs_value == (*op == Op::OrOr) {
+                        return Ok(Value::Bool(lhs_value));
+                    }
+                    let rhs_value = self.eval_expr(*rhs).await?;
+                    Ok(Value::Bool(self.expect_bool(rhs_value, *rhs)?))
+                }
+
+                ExprData::Op(lhs, op, rhs) => {
+                    let lhs = self.eval_expr(*lhs).await?;
+                    let rhs = self.eval_expr(*rhs).await?;
+                    self.apply_op(lhs, *op, rhs, expr)
+                }
+
+                ExprData::OpEq(place_expr, op, rhs) => {
+                    // The place is evaluated only once: the result is written
+                    // back to the same place that the old value was read from.
+                    let place = self.eval_place(*place_expr, Access::Write).await?;
+                    let lhs = self.peek_place(&place, *place_expr)?;
+                    let rhs = self.eval_expr(*rhs).await?;
+                    if let Place::Field { owner, .. } | Place::Element { owner, .. } = &place {
+                        // Evaluating `rhs` may have cancelled the lease on the owner.
+                        self.check_valid(*owner, *place_expr)?;
+                    }
+                    let value = self.apply_op(lhs, *op, rhs, expr)?;
+                    self.assign_place(&place, *place_expr, value)?;
+                    Ok(Value::Unit)
+                }
+
+                ExprData::Assign(place, rhs) => {
+                    let value = self.eval_expr(*rhs).await?;
+                    self.assign(*place, value).await?;
+                    Ok(Value::Unit)
+                }
+
+                ExprData::Error => {
+                    Err(self.error(expr, "cannot execute code that failed to parse"))
+                }
+            }
+        })
+    }
+
+    /// Evaluates `expr` as a place. Reaching a field requires `access`
+    /// to each object along the way.
+    fn eval_place(&mut self, expr: Expr, access: Access) -> BoxFuture<'_, eyre::Result<Place>> {
+        Box::pin(async move {
+            let ast = self.frame().ast;
+            match &ast.tables[expr] {
+                ExprData::Id(name) => match self.frame().resolutions.definition(expr) {
+                    Some(definition @ (Definition::Local(_) | Definition::Parameter(_)))
+                        if self.local(definition).is_some() =>
+                    {
+                        Ok(Place::Local(definition))
+                    }
+                    Some(Definition::Local(_) | Definition::Parameter(_)) | None => {
+                        // Variables of enclosing functions are only reachable
+                        // when captured by the closure being called.
+                        match self.captured_by(*name) {
+                            Some(closure) => Ok(Place::Capture(closure, *name)),
+                            None => Err(self.error(
+                                expr,
+                                format!(
+                                    "no variable, function, or class named `{}`",
+                                    name.as_str(self.db)
+                                ),
+                            )),
+                        }
+                    }
+                    Some(definition) => Ok(Place::Temporary(self.read_item(definition))),
+                },
+                ExprData::Dot(base, field) => {
+                    let (owner, path) = self.eval_owner(*base, *field, access).await?;
+                    let atomic =
+                        path.joint && self.field_mode(owner.object, *field) == StorageMode::Atomic;
+                    if !atomic {
+                        return Ok(Place::Field {
+                            owner,
+                            field: *field,
+                            path,
+                            atomic,
+                        });
+                    }
+
+                    if self.transaction.is_none() {
+                        return Err(self.error(
+                            expr,
+                            "access to shared, atomic field outside of atomic block",
+                        ));
+                    }
+
+                    // Within a transaction, the contents of an atomic field
+                    // are treated as if we had unique access to them.
+                    Ok(Place::Field {
+                        owner,
+                        field: *field,
+                        path: Path {
+                            joint: false,
+                            ..path
+                        },
+                        atomic,
+                    })
+                }
+                ExprData::Index(base, index) => {
+                    // The index is evaluated first, so that doing so cannot
+                    // cancel the access to the list recorded below.
+                    let index_value = self.eval_expr(*index).await?;
+                    let (owner, path) = self.eval_sequence(*base, access).await?;
+                    let index = self.element_index(owner.object, index_value, *index)?;
+                    Ok(Place::Element { owner, index, path })
+                }
+                ExprData::Give(expr) | ExprData::Parenthesized(expr) => {
+                    self.eval_place(*expr, access).await
+                }
+                _ => Ok(Place::Temporary(self.eval_expr(expr).await?)),
+            }
+        })
+    }
+
+    /// Evaluates `expr`, the owner of `field`, to a reference and records
+    /// an `access` to it. Writing a shared, atomic field only reads its
+    /// owner, since the write itself goes through the transaction.
+    async fn eval_owner(
+        &mut self,
+        expr: Expr,
+        field: Word,
+        access: Access,
+    ) -> eyre::Result<(Reference, Path)> {
+        let place = self.eval_place(expr, access).await?;
+        let reference = match self.peek_place(&place, expr)? {
+            Value::Reference(reference) => reference,
+            _ => return Err(self.error(expr, "expected an object")),
+        };
+        let path = place
+            .path()
+            .through(self.heap().permission(reference.permission));
+        let access =
+            if path.joint && self.field_mode(reference.object, field) == StorageMode::Atomic {
+                Access::Read
+            } else {
+                access
+            };
+        self.check_access(reference, access, path, expr)?;
+        Ok((reference, path))
+    }
+
+    /// Evaluates `expr`, a list or tuple being indexed, to a reference
+    /// and records an `access` to it.
+    async fn eval_sequence(
+        &mut self,
+        expr: Expr,
+        access: Access,
+    ) -> eyre::Result<(Reference, Path)> {
+        let place = self.eval_place(expr, access).await?;
+        let reference = match self.peek_place(&place, expr)? {
+            Value::Reference(reference) => reference,
+            _ => return Err(self.error(expr, "expected a list or tuple")),
+        };
+        let path = place
+            .path()
+            .through(self.heap().permission(reference.permission));
+        self.check_access(reference, access, path, expr)?;
+        Ok((reference, path))
+    }
+
+    /// Converts `value`, the result of the index expression `expr`,
+    /// into an index of an element of `object`.
+    fn element_index(&self, object: Object, value: Value, expr: Expr) -> eyre::Result<usize> {
+        let len = match self.heap().data(object) {
+            ObjectData::List(elements) | ObjectData::Tuple(elements) => elements.len(),
+            _ => return Err(self.error(expr, "can only index lists and tuples")),
+        };
+        let index = match value {
+            Value::Int(index) => index,
+            _ => return Err(self.error(expr, "index must be an integer")),
+        };
+        match usize::try_from(index) {
+            Ok(index) if index < len => Ok(index),
+            _ => Err(self.error(
+                expr,
+                format!("index {index} is out of bounds for length {len}"),
+            )),
+        }
+    }
+
+    /// Errors if `reference` may not be used, e.g. because its lease was cancelled,
+    /// pointing out where that happened.
+    fn check_valid(&self, reference: Reference, expr: Expr) -> eyre::Result<()> {
+        match self.heap().permission(reference.permission).state {
+            PermissionState::Valid => Ok(()),
+            PermissionState::Expired(reason, span) => {
+                let label = Label {
+                    span,
+                    message: reason.label().to_string(),
+                };
+                Err(self.error_with_labels(self.span(expr), reason.message(), vec![label]))
+            }
+        }
+    }
+
+    /// Checks that `reference`, reached via `path`, permits `access` and
+    /// records the access, cancelling any leases that conflict with it.
+    fn check_access(
+        &mut self,
+        reference: Reference,
+        access: Access,
+        path: Path,
+        expr: Expr,
+    ) -> eyre::Result<()> {
+        self.check_valid(reference, expr)?;
+
+        if access == Access::Write && path.joint {
+            return Err(self.error(expr, "cannot write to a shared object"));
+        }
+
+        let span = self.span(expr);
+        self.heap_mut().access(reference.permission, access, span);
+        Ok(())
+    }
+
+    /// Storage mode with which `field` was declared in the class of `object`.
+    /// Fields that the class does not declare are treated as shared.
+    fn field_mode(&self, object: Object, field: Word) -> StorageMode {
+        let class = match self.heap().data(object) {
+            ObjectData::Instance(instance) => instance.class,
+            _ => return StorageMode::Shared,
+        };
+        class
+            .fields(self.db)
+            .iter()
+            .find(|f| f.name(self.db) == field)
+            .map(|f| f.mode(self.db))
+            .unwrap_or(StorageMode::Shared)
+    }
+
+    /// Reads the value stored in `place` without giving it away.
+    fn peek_place(&mut self, place: &Place, expr: Expr) -> eyre::Result<Value> {
+        match place {
+            Place::Local(definition) => Ok(self.local(*definition).unwrap().value),
+            Place::Capture(closure, name) => match self.heap().data(*closure) {
+                ObjectData::Closure(closure) => Ok(closure.captures[name]),
+                _ => unreachable!("capture of something that is not a closure"),
+            },
+            Place::Field {
+                owner,
+                field,
+                atomic,
+                ..
+            } => {
+                let value = self.read_field(owner.object, *field, expr)?;
+                if *atomic {
+                    Ok(self.transaction_read(owner.object, *field, value))
+                } else {
+                    Ok(value)
+                }
+            }
+            Place::Element { owner, index, .. } => match self.heap().data(owner.object) {
+                ObjectData::List(elements) | ObjectData::Tuple(elements) => Ok(elements[*index]),
+                _ => unreachable!("element of something that is not a list or tuple"),
+            },
+            Place::Temporary(value) => Ok(*value),
+        }
+    }
+
+    /// Overwrites the value in `place`; no permission checks are performed.
+    fn poke_place(&mut self, place: &Place, value: Value) {
+        match place {
+            Place::Local(definition) => self.local_mut(*definition).unwrap().value = value,
+            Place::Capture(closure, name) => {
+                if let ObjectData::Closure(closure) = self.heap_mut().data_mut(*closure) {
+                    closure.captures[name] = value;
+                }
+            }
+            Place::Field {
+                owner,
+                field,
+                atomic: true,
+                ..
+            } => {
+                let transaction = self.transaction.as_mut().unwrap();
+                transaction.writes.insert((owner.object, *field), value);
+            }
+            Place::Field {
+                owner,
+                field,
+                atomic: false,
+                ..
+            } => self.write_field(owner.object, *field, value),
+            Place::Element { owner, index, .. } => {
+                if let ObjectData::List(elements) = self.heap_mut().data_mut(owner.object) {
+                    elements[*index] = value;
+                }
+            }
+            Place::Temporary(_) => {}
+        }
+    }
+
+    /// Reads the committed value of `field` in `object`.
+    fn read_field(&self, object: Object, field: Word, expr: Expr) -> eyre::Result<Value> {
+        match self.heap().data(object) {
+            ObjectData::Instance(instance) => {
+                instance.fields.get(&field).copied().ok_or_else(|| {
+                    self.error(
+                        expr,
+                        format!(
+                            "`{}` has no field named `{}`",
+                            instance.class.name(self.db).as_str(self.db),
+                            field.as_str(self.db)
+                        ),
+                    )
+                })
+            }
+            _ => Err(self.error(
+                expr,
+                format!(
+                    "cannot access field `{}` of something that is not a class instance",
+                    field.as_str(self.db)
+                ),
+            )),
+        }
+    }
+
+    fn write_field(&mut self, object: Object, field: Word, value: Value) {
+        if let ObjectData::Instance(instance) = self.heap_mut().data_mut(object) {
+            if let Some(slot) = instance.fields.get_mut(&field) {
+                *slot = value;
+            }
+        }
+    }
+
+    /// Reads an atomic field within the current transaction: a value written
+    /// earlier in the transaction wins, otherwise the committed value is used
+    /// and its version is recorded so that conflicts can be detected.
+    fn transaction_read(&mut self, object: Object, field: Word, committed: Value) -> Value {
+        let version = self.heap().atomic_version(object, field);
+        let transaction = self.transaction.as_mut().unwrap();
+        if let Some(&value) = transaction.writes.get(&(object, field)) {
+            return value;
+        }
+        transaction.reads.entry((object, field)).or_insert(version);
+        committed
+    }
+
+    /// Applies the writes buffered by `transaction`, unless another transaction
+    /// has committed to one of the fields that it read, in which case nothing
+    /// is written and `false` is returned.
+    fn commit(&mut self, transaction: Transaction) -> bool {
+        let conflict = transaction
+            .reads
+            .iter()
+            .any(|(&(object, field), &version)| {
+                self.heap().atomic_version(object, field) != version
+            });
+        if conflict {
+            return false;
+        }
+
+        for ((object, field), value) in transaction.writes {
+            self.write_field(object, field, value);
+            self.heap_mut().bump_atomic_version(object, field);
+        }
+        true
+    }
+
+    /// Evaluates `expr` and gives away its value. Owned values are moved
+    /// out of their place, leased values are subleased, and shared values
+    /// are copied.
+    async fn give(&mut self, expr: Expr) -> eyre::Result<Value> {
+        let place = self.eval_place(expr, Access::Read).await?;
+        let value = self.peek_place(&place, expr)?;
+        let reference = match value {
+            Value::Reference(reference) => reference,
+            _ => return Ok(value),
+        };
+        self.check_valid(reference, expr)?;
+
+        let span = self.span(expr);
+        let path = place
+            .path()
+            .through(self.heap().permission(reference.permission));
+        if path.joint {
+            Ok(self.share_reference(reference, span))
+        } else if path.leased {
+            let permission = self.heap_mut().lease(reference.permission, span);
+            Ok(Value::Reference(Reference {
+                permission,
+                ..reference
+            }))
+        } else {
+            let permission = self
+                .heap_mut()
+                .expired_permission(ExpiryReason::Given, span);
+            self.poke_place(
+                &place,
+                Value::Reference(Reference {
+                    permission,
+                    ..reference
+                }),
+            );
+            Ok(value)
+        }
+    }
+
+    /// Evaluates `expr` and leases its value. Leasing something that is
+    /// shared is the same as sharing it.
+    async fn lease(&mut self, expr: Expr) -> eyre::Result<Value> {
+        let place = self.eval_place(expr, Access::Read).await?;
+        let value = self.peek_place(&place, expr)?;
+        let reference = match value {
+            Value::Reference(reference) => reference,
+            _ => return Ok(value),
+        };
+        self.check_valid(reference, expr)?;
+
+        let span = self.span(expr);
+        let path = place
+            .path()
+            .through(self.heap().permission(reference.permission));
+        if path.joint {
+            Ok(self.share_reference(reference, span))
+        } else {
+            let permission = self.heap_mut().lease(reference.permission, span);
+            Ok(Value::Reference(Reference {
+                permission,
+                ..reference
+            }))
+        }
+    }
+
+    /// Evaluates `expr` and shares its value. A unique permission is
+    /// converted in place into a joint one (`my` to `our`, `leased` to `shared`).
+    async fn share(&mut self, expr: Expr) -> eyre::Result<Value> {
+        let place = self.eval_place(expr, Access::Read).await?;
+        let value = self.peek_place(&place, expr)?;
+        let reference = match value {
+            Value::Reference(reference) => reference,
+            _ => return Ok(value),
+        };
+        self.check_valid(reference, expr)?;
+
+        let span = self.span(expr);
+        let path = place
+            .path()
+            .through(self.heap().permission(reference.permission));
+        if path.joint {
+            Ok(self.share_reference(reference, span))
+        } else {
+            Ok(self.share_value(value, span))
+        }
+    }
+
+    /// Produces a shared copy of the value in the place `expr` without changing
+    /// how that value is owned: unlike `expr.share`, a uniquely owned value is
+    /// shared-leased rather than converted into a jointly owned one.
+    async fn read_shared(&mut self, expr: Expr) -> eyre::Result<Value> {
+        let place = self.eval_place(expr, Access::Read).await?;
+        let value = self.peek_place(&place, expr)?;
+        let reference = match value {
+            Value::Reference(reference) => reference,
+            _ => return Ok(value),
+        };
+        self.check_valid(reference, expr)?;
+        let span = self.span(expr);
+        Ok(self.share_reference(reference, span))
+    }
+
+    /// Converts `value`, which must not be used anywhere else, into a shared value.
+    fn share_value(&mut self, value: Value, span: Span) -> Value {
+        if let Value::Reference(reference) = value {
+            if self.heap().permission(reference.permission).is_valid() {
+                self.heap_mut().share(reference.permission, span);
+            }
+        }
+        value
+    }
+
+    /// Produces a shared copy of `reference`: joint permissions are simply
+    /// duplicated, unique ones are shared-leased.
+    fn share_reference(&mut self, reference: Reference, span: Span) -> Value {
+        if self.heap().permission(reference.permission).joint == Joint::Yes {
+            return Value::Reference(reference);
+        }
+
+        let permission = self.heap_mut().shared_lease(reference.permission, span);
+        Value::Reference(Reference {
+            permission,
+            ..reference
+        })
+    }
+
+    /// Drops `value`; if it was uniquely owned or leased, its permission
+    /// expires, cancelling any leases taken from it.
+    fn drop_value(&mut self, value: Value, span: Span) {
+        if let Value::Reference(reference) = value {
+            if self.heap().permission(reference.permission).joint == Joint::No {
+                self.heap_mut()
+                    .expire(reference.permission, ExpiryReason::Dropped, span);
+            }
+        }
+    }
+
+    /// Stores `value` into the place named by the expression `place`
+    /// (a local variable or a field).
+    async fn assign(&mut self, place_expr: Expr, value: Value) -> eyre::Result<()> {
+        let place = self.eval_place(place_expr, Access::Write).await?;
+        self.assign_place(&place, place_expr, value)
+    }
+
+    /// Stores `value` into `place`, which `place_expr` evaluated to,
+    /// if that place may be assigned to.
+    fn assign_place(&mut self, place: &Place, place_expr: Expr, value: Value) -> eyre::Result<()> {
+        match place {
+            Place::Local(definition) => {
+                let local = self.local(*definition).unwrap();
+                match local.mode {
+                    StorageMode::Var | StorageMode::Atomic => {}
+                    StorageMode::Shared => {
+                        return Err(self.error(
+                            place_expr,
+                            format!(
+                                "cannot assign to `{}`, which was not declared with `var`",
+                                local.name.as_str(self.db)
+                            ),
+                        ))
+                    }
+                }
+            }
+            Place::Capture(closure, name) => {
+                if let ObjectData::Closure(Closure {
+                    mode: CaptureMode::Share,
+                    ..
+                }) = self.heap().data(*closure)
+                {
+                    return Err(self.error(
+                        place_expr,
+                        format!(
+                            "cannot assign to `{}`, which was captured by a shared closure",
+                            name.as_str(self.db)
+                        ),
+                    ));
+                }
+            }
+            Place::Field { owner, field, .. } => {
+                if self.field_mode(owner.object, *field) == StorageMode::Shared {
+                    return Err(self.error(
+                        place_expr,
+                        format!(
+                            "cannot assign to `{}`, which is a shared field",
+                            field.as_str(self.db)
+                        ),
+                    ));
+                }
+            }
+            Place::Element { owner, .. } => {
+                if let ObjectData::Tuple(_) = self.heap().data(owner.object) {
+                    return Err(self.error(place_expr, "cannot assign to an element of a tuple"));
+                }
+            }
+            Place::Temporary(_) => {
+                return Err(self.error(place_expr, "cannot assign to this expression"));
+            }
+        }
+
+        let old_value = self.peek_place(place, place_expr)?;
+        self.drop_value(old_value, self.span(place_expr));
+        self.poke_place(place, value);
+        Ok(())
+    }
+
+    /// The local variable or parameter of the current frame with the given definition.
+    fn local(&self, definition: Definition) -> Option<&Local> {
+        self.frame()
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&definition))
+    }
+
+    fn local_mut(&mut self, definition: Definition) -> Option<&mut Local> {
+        self.frame_mut()
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(&definition))
+    }
+
+    /// If the current frame is a call to a closure that captured `name`,
+    /// returns that closure.
+    fn captured_by(&self, name: Word) -> Option<Object> {
+        let closure = self.frame().closure?;
+        match self.heap().data(closure) {
+            ObjectData::Closure(data) if data.captures.contains_key(&name) => Some(closure),
+            _ => None,
+        }
+    }
+
+    /// Creates an object for the item or intrinsic `definition`.
+    fn read_item(&mut self, definition: Definition) -> Value {
+        let data = match definition {
+            Definition::Class(class) => ObjectData::Class(class),
+            Definition::Function(function) => ObjectData::Function(function),
+            Definition::Intrinsic(intrinsic) => ObjectData::Intrinsic(intrinsic),
+            Definition::Local(_) | Definition::Parameter(_) => {
+                unreachable!("variable read as an item")
+            }
+        };
+        self.heap_mut().new_object(data)
+    }
+
+    async fn call(
+        &mut self,
+        callee: Value,
+        arguments: Vec<(Word, Value)>,
+        expr: Expr,
+    ) -> eyre::Result<Value> {
+        let callee = match callee {
+            Value::Reference(reference) => match self.heap().data(reference.object) {
+                &ObjectData::Class(class) => Some(Callee::Class(class)),
+                &ObjectData::Function(function) => Some(Callee::Function(function)),
+                &ObjectData::Intrinsic(intrinsic) => Some(Callee::Intrinsic(intrinsic)),
+                ObjectData::Closure(closure) => Some(Callee::Closure(
+                    reference.object,
+                    closure.parameters.clone(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        };
+        let callee = callee
+            .ok_or_else(|| self.error(expr, "can only call functions, closures and classes"))?;
+        let arguments = self.match_arguments(&callee, arguments, expr)?;
+
+        match callee {
+            Callee::Class(class) => {
+                let instance = Instance {
+                    class,
+                    fields: arguments.into_iter().collect(),
+                };
+                Ok(self.heap_mut().new_object(ObjectData::Instance(instance)))
+            }
+            Callee::Function(function) => match function.effect(self.db) {
+                Effect::Async => {
+                    let thunk = Thunk::Function(function, arguments);
+                    Ok(self.heap_mut().new_object(ObjectData::Thunk(thunk)))
+                }
+                Effect::None => self.call_function(function, arguments).await,
+            },
+            Callee::Intrinsic(intrinsic) => match intrinsic.effect() {
+                Effect::Async => {
+                    let thunk = Thunk::Intrinsic(intrinsic, arguments);
+                    Ok(self.heap_mut().new_object(ObjectData::Thunk(thunk)))
+                }
+                Effect::None => {
+                    let span = self.span(expr);
+                    self.call_intrinsic(intrinsic, arguments, span)
+                }
+            },
+            Callee::Closure(object, _) => self.call_closure(object, arguments).await,
+        }
+    }
+
+    /// Checks that `arguments` supply each of the callee's parameters exactly
+    /// once, and returns them in the order in which the parameters were declared.
+    fn match_arguments(
+        &self,
+        callee: &Callee,
+        mut arguments: Vec<(Word, Value)>,
+        expr: Expr,
+    ) -> eyre::Result<Vec<(Word, Value)>> {
+        let parameters = callee.parameters(self.db);
+        let names: Vec<Word> = arguments.iter().map(|&(name, _)| name).collect();
+        if let Some(mismatch) = match_arguments(&parameters, &names).first() {
+            let message = mismatch.message(self.db, &callee.name(self.db), &names);
+            return Err(self.error(expr, message));
+        }
+
+        arguments.sort_by_key(|&(name, _)| parameters.iter().position(|&p| p == name));
+        Ok(arguments)
+    }
+
+    /// Returns the thunk that `value` refers to, if any.
+    fn thunk(&self, value: Value) -> Option<Thunk> {
+        match value {
+            Value::Reference(reference) => match self.heap().data(reference.object) {
+                ObjectData::Thunk(thunk) => Some(thunk.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Runs the thunk `value` to completion. Awaiting is where tasks are
+    /// interleaved, so other tasks get a chance to run first.
+    async fn await_thunk(&mut self, value: Value, expr: Expr) -> eyre::Result<Value> {
+        let thunk = self
+            .thunk(value)
+            .ok_or_else(|| self.error(expr, "can only await a thunk"))?;
+        yield_now().await;
+        let span = self.span(expr);
+        self.run_thunk(thunk, span).await
+    }
+
+    fn call_intrinsic(
+        &mut self,
+        intrinsic: Intrinsic,
+        arguments: Vec<(Word, Value)>,
+        span: Span,
+    ) -> eyre::Result<Value> {
+        let [(_, argument)] = <[_; 1]>::try_from(arguments).map_err(|_| {
+            self.error_at(span, format!("`{intrinsic}` expects exactly one argument"))
+        })?;
+
+        match intrinsic {
+            Intrinsic::Print => {
+                let text = self.stringify(argument);
+                writeln!(self.interpreter.stdout.borrow_mut(), "{text}")?;
+                Ok(Value::Unit)
+            }
+            Intrinsic::Spawn => {
+                let thunk = self
+                    .thunk(argument)
+                    .ok_or_else(|| self.error_at(span, format!("`{intrinsic}` expects a thunk")))?;
+                self.interpreter.spawned.borrow_mut().push((thunk, span));
+                Ok(Value::Unit)
+            }
+        }
+    }
+
+    fn apply_op(&mut self, lhs: Value, op: Op, rhs: Value, expr: Expr) -> eyre::Result<Value> {
+        if let (Value::Reference(lhs), Op::Plus, Value::Reference(rhs)) = (lhs, op, rhs) {
+            let string = match (self.heap().data(lhs.object), self.heap().data(rhs.object)) {
+                (ObjectData::String(lhs), ObjectData::String(rhs)) => Some(format!("{lhs}{rhs}")),
+                _ => None,
+            };
+            if let Some(string) = string {
+                return Ok(self.heap_mut().new_object(ObjectData::String(string)));
+            }
+        }
+
+        if let Op::EqualEqual
+        | Op::NotEqual
+        | Op::LessThan
+        | Op::LessEqual
+        | Op::GreaterThan
+        | Op::GreaterEqual = op
+        {
+            return self.compare(lhs, op, rhs, expr);
+        }
+
+        match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => {
+                let result = match op {
+                    Op::Plus => lhs.checked_add(rhs),
+                    Op::Minus => lhs.checked_sub(rhs),
+                    Op::Times => lhs.checked_mul(rhs),
+                    Op::DividedBy if rhs == 0 => return Err(self.error(expr, "division by zero")),
+                    Op::DividedBy => lhs.checked_div(rhs),
+                    _ => return Err(self.error(expr, format!("cannot apply `{op}` to integers"))),
+                };
+                result
+                    .map(Value::Int)
+                    .ok_or_else(|| self.error(expr, format!("integer overflow in `{op}`")))
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => match op {
+                Op::Plus => Ok(Value::Float(lhs + rhs)),
+                Op::Minus => Ok(Value::Float(lhs - rhs)),
+                Op::Times => Ok(Value::Float(lhs * rhs)),
+                Op::DividedBy => Ok(Value::Float(lhs / rhs)),
+                _ => Err(self.error(expr, format!("cannot apply `{op}` to floats"))),
+            },
+            _ => Err(self.error(expr, format!("cannot apply `{op}` to these values"))),
+        }
+    }
+
+    /// Applies a comparison operator. Numbers and strings can be ordered;
+    /// booleans and `()` can only be compared for equality.
+    fn compare(&self, lhs: Value, op: Op, rhs: Value, expr: Expr) -> eyre::Result<Value> {
+        let ordering = match (lhs, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(&rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(&rhs),
+            (Value::Bool(lhs), Value::Bool(rhs)) if matches!(op, Op::EqualEqual | Op::NotEqual) => {
+                Some(lhs.cmp(&rhs))
+            }
+            (Value::Unit, Value::Unit) if matches!(op, Op::EqualEqual | Op::NotEqual) => {
+                Some(Ordering::Equal)
+            }
+            (Value::Reference(lhs), Value::Reference(rhs)) => {
+                match (self.heap().data(lhs.object), self.heap().data(rhs.object)) {
+                    (ObjectData::String(lhs), ObjectData::String(rhs)) => Some(lhs.cmp(rhs)),
+                    _ => {
+                        return Err(self.error(expr, format!("cannot apply `{op}` to these values")))
+                    }
+                }
+            }
+            _ => return Err(self.error(expr, format!("cannot apply `{op}` to these values"))),
+        };
+
+        // `ordering` is `None` only when comparing against NaN, which is unequal to everything.
+        let result = match op {
+            Op::EqualEqual => ordering == Some(Ordering::Equal),
+            Op::NotEqual => ordering != Some(Ordering::Equal),
+            Op::LessThan => ordering == Some(Ordering::Less),
+            Op::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Op::GreaterThan => ordering == Some(Ordering::Greater),
+            Op::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            _ => unreachable!("`{op}` is not a comparison"),
+        };
+        Ok(Value::Bool(result))
+    }
+
+    fn expect_bool(&self, value: Value, expr: Expr) -> eyre::Result<bool> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(self.error(expr, "expected a boolean")),
+        }
+    }
+
+    /// Converts `value` into the string that `print` would display.
+    fn stringify(&self, value: Value) -> String {
+        let reference = match value {
+            Value::Unit => return "()".to_string(),
+            Value::Bool(b) => return b.to_string(),
+            Value::Int(i) => return i.to_string(),
+            Value::Float(f) => return format!("{f:?}"),
+            Value::Reference(reference) => reference,
+        };
+
+        if !self.heap().permission(reference.permission).is_valid() {
+            return "(expired)".to_string();
+        }
+
+        match self.heap().data(reference.object) {
+            ObjectData::String(s) => s.clone(),
+            ObjectData::Instance(instance) => {
+                let fields: Vec<String> = instance
+                    .fields
+                    .iter()
+                    .map(|(name, &value)| {
+                        format!("{}: {}", name.as_str(self.db), self.stringify(value))
+                    })
+                    .collect();
+                format!(
+                    "{}({})",
+                    instance.class.name(self.db).as_str(self.db),
+                    fields.join(", ")
+                )
+            }
+            ObjectData::List(elements) => {
+                let elements: Vec<String> = elements.iter().map(|&e| self.stringify(e)).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            ObjectData::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|&e| self.stringify(e)).collect();
+                match &elements[..] {
+                    [element] => format!("({element},)"),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            ObjectData::Class(class) => class.name(self.db).as_str(self.db).to_string(),
+            ObjectData::Function(function) => function.name(self.db).as_str(self.db).to_string(),
+            ObjectData::Intrinsic(intrinsic) => intrinsic.str().to_string(),
+            ObjectData::Thunk(_) => "<thunk>".to_string(),
+            ObjectData::Closure(_) => "<closure>".to_string(),
+        }
+    }
+}
+
+impl Inspect for Task<'_> {
+    fn stack(&self) -> Vec<StackFrame> {
+        self.stack
+            .iter()
+            .map(|frame| StackFrame {
+                function: frame.function,
+                span: frame.position,
+            })
+            .collect()
+    }
+
+    fn heap_graph(&self, index: usize) -> HeapGraph {
+        let frame = &self.stack[index];
+        let heap = self.heap();
+        let captures = match frame.closure.map(|closure| heap.data(closure)) {
+            Some(ObjectData::Closure(closure)) => Some(&closure.captures),
+            _ => None,
+        };
+        let variables: Vec<(Word, Value)> = captures
+            .into_iter()
+            .flat_map(|captures| captures.iter().map(|(&name, &value)| (name, value)))
+            .chain(
+                frame
+                    .scopes
+                    .iter()
+                    .flat_map(|scope| scope.values().map(|local| (local.name, local.value))),
+            )
+            .collect();
+        HeapGraph::new(self.db, &heap, &variables)
+    }
+}
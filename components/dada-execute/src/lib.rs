@@ -0,0 +1,23 @@
+//! The `dada_execute` crate is a tree-walking interpreter for Dada programs.
+//! It walks the [`Ast`](dada_ir::code::Ast) produced by the parser directly,
+//! without any intermediate lowering.
+
+#![feature(trait_upcasting)]
+#![allow(incomplete_features)]
+
+mod breakpoint;
+mod captures;
+mod executor;
+mod heap;
+mod heap_graph;
+mod interpreter;
+mod observer;
+mod permission;
+
+pub trait Db: dada_parse::Db + dada_lex::Db + dada_ir::Db + dada_resolve::Db {}
+impl<T> Db for T where T: dada_parse::Db + dada_lex::Db + dada_ir::Db + dada_resolve::Db {}
+
+pub use breakpoint::breakpoint_statement;
+pub use heap_graph::{Entry, HeapGraph, HeapGraphFormat};
+pub use interpreter::{interpret, interpret_entry, interpret_with_observer};
+pub use observer::{Observer, StackFrame, Stop};
@@ -0,0 +1,111 @@
+//! Hooks that let tools (such as `dada run --heap-graph` and `dada debug`)
+//! watch a program while it executes.
+
+use std::ops::ControlFlow;
+
+use dada_ir::{func::Function, span::Span};
+
+use crate::heap_graph::HeapGraph;
+
+/// Notified by the interpreter at interesting points during execution.
+/// Every method has a default implementation that does nothing.
+/// Returning `ControlFlow::Break` stops the program: every task is
+/// abandoned and the interpreter returns successfully.
+pub trait Observer {
+    /// Invoked just before each expression in a block (or function body)
+    /// is evaluated; `stop.span` is the span of that expression.
+    fn before_statement(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let _ = stop;
+        ControlFlow::Continue(())
+    }
+
+    /// Invoked just before any expression is evaluated, including statements
+    /// (after [`Observer::before_statement`]) and the expressions within them;
+    /// `stop.span` is the span of that expression. The parts of a place that
+    /// is being accessed, such as `p` in `p.x`, are not evaluated on their own.
+    fn before_expr(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let _ = stop;
+        ControlFlow::Continue(())
+    }
+
+    /// Invoked once `stop.function` has evaluated its body, just before
+    /// its local variables are dropped; `stop.span` is the function's name.
+    fn function_exit(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let _ = stop;
+        ControlFlow::Continue(())
+    }
+}
+
+impl Observer for () {}
+
+/// A point at which the interpreter pauses to notify an [`Observer`].
+pub struct Stop<'a> {
+    /// The function that is executing.
+    pub function: Function,
+
+    /// The code about to execute.
+    pub span: Span,
+
+    /// Number of active calls in the current task, including `function`.
+    pub depth: usize,
+
+    /// Number of expressions in the current call that are being evaluated,
+    /// including the one that `before_expr` is about to evaluate. Together
+    /// with `depth`, this tells whether one stop is nested within another.
+    pub nesting: usize,
+
+    task: &'a dyn Inspect,
+}
+
+/// One active call in the stopped task.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StackFrame {
+    pub function: Function,
+
+    /// The statement that the call is executing; for callers, this is
+    /// the statement containing the call.
+    pub span: Span,
+}
+
+/// Lets a [`Stop`] examine the task that is paused.
+pub(crate) trait Inspect {
+    /// The active calls, outermost first.
+    fn stack(&self) -> Vec<StackFrame>;
+
+    /// Snapshot of the locals of the call at `index` in [`Inspect::stack`].
+    fn heap_graph(&self, index: usize) -> HeapGraph;
+}
+
+impl<'a> Stop<'a> {
+    pub(crate) fn new(
+        function: Function,
+        span: Span,
+        depth: usize,
+        nesting: usize,
+        task: &'a dyn Inspect,
+    ) -> Self {
+        Self {
+            function,
+            span,
+            depth,
+            nesting,
+            task,
+        }
+    }
+
+    /// The active calls in the current task, outermost first.
+    pub fn stack(&self) -> Vec<StackFrame> {
+        self.task.stack()
+    }
+
+    /// Snapshot of the current function's variables and the objects
+    /// reachable from them. Only built when requested.
+    pub fn heap_graph(&self) -> HeapGraph {
+        self.task.heap_graph(self.depth - 1)
+    }
+
+    /// Like [`Stop::heap_graph`], but for the call at `index` in [`Stop::stack`].
+    pub fn frame_heap_graph(&self, index: usize) -> HeapGraph {
+        self.task.heap_graph(index)
+    }
+}
@@ -0,0 +1,107 @@
+//! Runtime permissions. Every reference to an object carries a [`Permission`]
+//! that says what the holder may do with the object:
+//!
+//! | joint | leased | written as  | meaning                                    |
+//! | ----- | ------ | ----------- | ------------------------------------------ |
+//! | no    | no     | `my`        | unique owner                               |
+//! | yes   | no     | `our`       | one of several joint owners                |
+//! | no    | yes    | `leased(p)` | unique, temporary access granted by `p`    |
+//! | yes   | yes    | `shared(p)` | shared, temporary access granted by `p`    |
+//!
+//! Leases are tracked as *tenants* of the permission they were leased from
+//! (the *lessor*). When the lessor is used in a way that conflicts with the
+//! terms of a lease, the lease (and any subleases) is revoked.
+
+use dada_ir::span::Span;
+
+dada_id::id!(pub(crate) struct Permission);
+
+#[derive(Clone, Debug)]
+pub(crate) struct PermissionData {
+    pub(crate) joint: Joint,
+    pub(crate) leased: Leased,
+
+    /// For leased permissions, the permission they were leased from.
+    pub(crate) lessor: Option<Permission>,
+
+    /// Permissions leased from this one.
+    pub(crate) tenants: Vec<Permission>,
+
+    pub(crate) state: PermissionState,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Joint {
+    No,
+    Yes,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Leased {
+    No,
+    Yes,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum PermissionState {
+    Valid,
+
+    /// The permission can no longer be used; the span indicates where
+    /// that happened.
+    Expired(ExpiryReason, Span),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum ExpiryReason {
+    /// The value was given away to somebody else.
+    Given,
+
+    /// The lessor took an action that ended the lease.
+    Cancelled,
+
+    /// The owner went out of scope.
+    Dropped,
+}
+
+/// The kind of access being made through a permission.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Access {
+    Read,
+    Write,
+}
+
+impl PermissionData {
+    pub(crate) fn new(joint: Joint, leased: Leased, lessor: Option<Permission>) -> Self {
+        PermissionData {
+            joint,
+            leased,
+            lessor,
+            tenants: vec![],
+            state: PermissionState::Valid,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        matches!(self.state, PermissionState::Valid)
+    }
+}
+
+impl ExpiryReason {
+    /// Message reported when an expired permission is used.
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            ExpiryReason::Given => "this value has been given away",
+            ExpiryReason::Cancelled => "your lease to this object was cancelled",
+            ExpiryReason::Dropped => "this object has been dropped",
+        }
+    }
+
+    /// Label for the place where the permission expired.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ExpiryReason::Given => "given away here",
+            ExpiryReason::Cancelled => "lease cancelled here",
+            ExpiryReason::Dropped => "dropped here",
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Matching the named arguments of a call against the parameters of the
+//! callee. Used both to check calls statically and by the interpreter,
+//! so that the two report the same mismatches.
+
+use crate::word::Word;
+
+/// A way in which the arguments of a call fail to match the callee's parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArgumentMismatch {
+    /// The argument at `index` names no parameter.
+    UnknownParameter { index: usize },
+
+    /// The argument at `index` names the same parameter as an earlier argument.
+    Repeated { index: usize },
+
+    /// No argument names `parameter`.
+    Missing { parameter: Word },
+}
+
+/// Compares `arguments`, the names of a call's arguments in the order they were
+/// given, with `parameters`, the names that the callee declares. Returns every
+/// mismatch: those of the arguments in order, then the missing parameters in
+/// declaration order.
+pub fn match_arguments(parameters: &[Word], arguments: &[Word]) -> Vec<ArgumentMismatch> {
+    let mut mismatches = vec![];
+    for (index, name) in arguments.iter().enumerate() {
+        if !parameters.contains(name) {
+            mismatches.push(ArgumentMismatch::UnknownParameter { index });
+        } else if arguments[..index].contains(name) {
+            mismatches.push(ArgumentMismatch::Repeated { index });
+        }
+    }
+    for &parameter in parameters {
+        if !arguments.contains(&parameter) {
+            mismatches.push(ArgumentMismatch::Missing { parameter });
+        }
+    }
+    mismatches
+}
+
+impl ArgumentMismatch {
+    /// Describes the mismatch, which was found in a call to `callee` with the given arguments.
+    pub fn message(self, db: &dyn crate::Db, callee: &str, arguments: &[Word]) -> String {
+        match self {
+            ArgumentMismatch::UnknownParameter { index } => format!(
+                "`{callee}` has no parameter named `{}`",
+                arguments[index].as_str(db)
+            ),
+            ArgumentMismatch::Repeated { index } => format!(
+                "argument `{}` was given more than once",
+                arguments[index].as_str(db)
+            ),
+            ArgumentMismatch::Missing { parameter } => format!(
+                "missing argument `{}` in call to `{callee}`",
+                parameter.as_str(db)
+            ),
+        }
+    }
+}
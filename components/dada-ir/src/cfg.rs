@@ -0,0 +1,218 @@
+//! The control-flow graph of a function body: the `Ast` lowered into basic
+//! blocks of simple statements, with temporaries for intermediate values and
+//! explicit drops when variables go out of scope.
+
+use dada_collections::IndexVec;
+use dada_id::id;
+
+use crate::{
+    code::{Block, CaptureMode, Expr, HasSpan, Spans},
+    definition::Definition,
+    func::Parameter,
+    number::Number,
+    op::Op,
+    span::Span,
+    storage_mode::StorageMode,
+    word::Word,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cfg {
+    pub locals: IndexVec<Local, LocalData>,
+    pub blocks: IndexVec<BasicBlock, BasicBlockData>,
+
+    /// The block where execution begins.
+    pub start: BasicBlock,
+
+    /// The local that holds the result of the function when it returns.
+    pub return_local: Local,
+}
+
+id!(pub struct Local);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalData {
+    /// The name of the variable, or `None` for temporaries.
+    pub name: Option<Word>,
+    pub mode: StorageMode,
+    pub origin: LocalOrigin,
+}
+
+/// Where a local comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LocalOrigin {
+    Parameter(Parameter),
+
+    /// A variable declared by the given `Var` expression.
+    Var(Expr),
+
+    /// A temporary holding the value of the given expression.
+    Temporary(Expr),
+
+    /// The local returned by `Cfg::return_local`.
+    Return,
+}
+
+id!(pub struct BasicBlock);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlockData {
+    pub statements: Vec<Statement>,
+    pub terminator: Terminator,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub origin: Origin,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatementKind {
+    /// Stores the value of the rvalue into the place, dropping its old value.
+    Assign(Place, Rvalue),
+
+    /// The local goes out of scope and its value is dropped.
+    Drop(Local),
+
+    /// Start of an `atomic` block.
+    StartAtomic,
+
+    /// End of an `atomic` block, including leaving it with `break` or `return`.
+    EndAtomic,
+}
+
+/// The source code that a statement was lowered from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Origin {
+    Expr(Expr),
+
+    /// The end of a block, for the drops of the variables declared in it.
+    Block(Block),
+}
+
+impl HasSpan for Origin {
+    fn span_in(self, spans: &Spans) -> &Span {
+        match self {
+            Origin::Expr(expr) => expr.span_in(spans),
+            Origin::Block(block) => block.span_in(spans),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Terminator {
+    Goto(BasicBlock),
+
+    /// Goes to the first block if the local is true, the second otherwise.
+    If(Local, BasicBlock, BasicBlock),
+
+    Return,
+
+    /// Execution cannot continue, e.g. after a `break` outside of a loop.
+    Error,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BasicBlock> {
+        match *self {
+            Terminator::Goto(target) => vec![target],
+            Terminator::If(_, if_true, if_false) => vec![if_true, if_false],
+            Terminator::Return | Terminator::Error => vec![],
+        }
+    }
+}
+
+/// A local, or a field or element reached from one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Place {
+    pub local: Local,
+    pub projections: Vec<Projection>,
+}
+
+impl From<Local> for Place {
+    fn from(local: Local) -> Self {
+        Place {
+            local,
+            projections: vec![],
+        }
+    }
+}
+
+impl Place {
+    /// The locals that are read to find the place: the local it starts
+    /// from and those holding indices.
+    pub fn locals(&self) -> Vec<Local> {
+        let indices = self.projections.iter().filter_map(|p| match *p {
+            Projection::Field(_) => None,
+            Projection::Index(index) => Some(index),
+        });
+        std::iter::once(self.local).chain(indices).collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Projection {
+    /// `.field`
+    Field(Word),
+
+    /// `[index]`, with the index stored in the local.
+    Index(Local),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rvalue {
+    Give(Place),
+    Lease(Place),
+    Share(Place),
+
+    Unit,
+    Number(Number),
+    String(Word),
+
+    /// A class, function or intrinsic.
+    Item(Definition),
+
+    /// The values in the places converted to strings and concatenated.
+    /// The places are only read: interpolating a variable does not give it away.
+    Concatenate(Vec<Place>),
+
+    Tuple(Vec<Local>),
+    List(Vec<Local>),
+
+    /// Calls the callee in the first local with the named arguments.
+    Call(Local, Vec<(Word, Local)>),
+
+    Await(Local),
+    Unary(Op, Local),
+    Op(Local, Op, Local),
+
+    /// Creates the closure defined by the given `Closure` expression, with
+    /// the values captured for each variable it uses from its surroundings.
+    Closure(CaptureMode, Expr, Vec<(Word, Local)>),
+
+    /// Code that failed to parse, or a name that could not be resolved.
+    Error,
+}
+
+impl Rvalue {
+    /// The locals that are read to compute the value.
+    pub fn locals(&self) -> Vec<Local> {
+        match self {
+            Rvalue::Give(place) | Rvalue::Lease(place) | Rvalue::Share(place) => place.locals(),
+            Rvalue::Unit
+            | Rvalue::Number(_)
+            | Rvalue::String(_)
+            | Rvalue::Item(_)
+            | Rvalue::Error => vec![],
+            Rvalue::Concatenate(places) => places.iter().flat_map(Place::locals).collect(),
+            Rvalue::Tuple(locals) | Rvalue::List(locals) => locals.clone(),
+            Rvalue::Call(callee, arguments) => std::iter::once(*callee)
+                .chain(arguments.iter().map(|&(_, argument)| argument))
+                .collect(),
+            Rvalue::Await(local) | Rvalue::Unary(_, local) => vec![*local],
+            Rvalue::Op(lhs, _, rhs) => vec![*lhs, *rhs],
+            Rvalue::Closure(_, _, captures) => captures.iter().map(|&(_, local)| local).collect(),
+        }
+    }
+}
@@ -4,7 +4,14 @@ salsa::entity2! {
     entity Class in crate::Jar {
         #[id] name: Word,
         name_span: Span,
+
+        /// The `T` in `class Box[T](value: T)`.
+        #[value ref] type_parameters: Vec<Word>,
+
         field_tokens: TokenTree,
+
+        /// Lines of the `///` comments before the class.
+        #[value ref] docs: Vec<Word>,
     }
 }
 
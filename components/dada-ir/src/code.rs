@@ -1,4 +1,7 @@
-use crate::{op::Op, span::Span, storage_mode::StorageMode, token_tree::TokenTree, word::Word};
+use crate::{
+    func::Parameter, number::Number, op::Op, span::Span, storage_mode::StorageMode,
+    token_tree::TokenTree, word::Word,
+};
 use dada_collections::IndexVec;
 use dada_id::{id, tables};
 
@@ -14,7 +17,7 @@ pub struct Ast {
     pub block: Block,
 }
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Spans {
     pub expr_spans: IndexVec<Expr, Span>,
     pub named_expr_spans: IndexVec<NamedExpr, NamedExprSpan>,
@@ -55,7 +58,19 @@ id!(pub struct Expr);
 pub enum ExprData {
     Id(Word),
     StringLiteral(Word),
+
+    /// `22`, `0xFF`, `2.5e3`, ...
+    NumberLiteral(Number),
+
+    /// `"The point is ({p.x}, {p.y})"`: the text and the embedded expressions,
+    /// whose values are converted to strings and concatenated.
+    Concatenate(Vec<Expr>),
+
     Dot(Expr, Word),
+
+    /// `list[index]`
+    Index(Expr, Expr),
+
     Await(Expr),
     Call(Expr, Vec<NamedExpr>),
     Share(Expr),
@@ -65,13 +80,36 @@ pub enum ExprData {
 
     Parenthesized(Expr),
 
+    /// `()`, `(a,)`, `(a, b)`
+    Tuple(Vec<Expr>),
+
+    /// `[a, b]`
+    List(Vec<Expr>),
+
     If(Expr, Expr, Option<Expr>),
     Loop(Expr),
     While(Expr, Expr),
 
-    // { ... } ==> closure?
+    /// `break` or `break value`
+    Break(Option<Expr>),
+
+    Continue,
+
+    /// `return` or `return value`
+    Return(Option<Expr>),
+
     Block(Block),
 
+    /// `give fn(x) { ... }`: a closure with the given parameters and body
+    /// (always a `Block`).
+    Closure(CaptureMode, Vec<Parameter>, Expr),
+
+    /// `atomic { ... }`
+    Atomic(Expr),
+
+    /// `-x`, `!x`
+    Unary(Op, Expr),
+
     Op(Expr, Op, Expr),
     OpEq(Expr, Op, Expr),
     Assign(Expr, Expr),
@@ -79,6 +117,19 @@ pub enum ExprData {
     Error,
 }
 
+/// How a closure captures the local variables that it uses.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum CaptureMode {
+    /// The closure takes ownership of the variables' values (`give fn`).
+    Give,
+
+    /// The closure leases the variables' values (`lease fn`).
+    Lease,
+
+    /// The closure shares the variables' values (`share fn`, or just `fn`).
+    Share,
+}
+
 impl HasSpan for Expr {
     fn span_in(self, spans: &Spans) -> &Span {
         &spans.expr_spans[self]
@@ -102,6 +153,7 @@ pub struct NamedExprData {
     pub expr: Expr,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NamedExprSpan {
     pub span: Span,
     pub name_span: Span,
@@ -0,0 +1,39 @@
+use dada_collections::Map;
+
+use crate::{
+    class::Class,
+    code::Expr,
+    func::{Function, Parameter},
+    intrinsic::Intrinsic,
+};
+
+/// The thing that a name used in a function body refers to.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Definition {
+    /// A local variable, declared by the given `Var` expression.
+    Local(Expr),
+
+    /// A parameter of the function, or of a closure within it.
+    Parameter(Parameter),
+
+    Class(Class),
+    Function(Function),
+    Intrinsic(Intrinsic),
+}
+
+/// The result of name resolution for a function body: the definition
+/// named by each `Id` expression. Names that could not be resolved
+/// have no entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Resolutions {
+    pub definitions: Map<Expr, Definition>,
+
+    /// The `Id` expressions whose names could not be resolved, in the order they appear.
+    pub unresolved: Vec<Expr>,
+}
+
+impl Resolutions {
+    pub fn definition(&self, expr: Expr) -> Option<Definition> {
+        self.definitions.get(&expr).copied()
+    }
+}
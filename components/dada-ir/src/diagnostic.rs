@@ -5,6 +5,16 @@ pub struct Diagnostic {
     pub filename: Word,
     pub span: Span,
     pub message: String,
+
+    /// Other locations in the same file that help explain the error,
+    /// such as where a conflicting lease was created.
+    pub labels: Vec<Label>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
 }
 
 #[salsa::accumulator(in crate::Jar)]
@@ -1,4 +1,6 @@
-use crate::{code::Code, span::Span, token_tree::TokenTree, word::Word};
+use crate::{
+    code::Code, span::Span, storage_mode::StorageMode, token_tree::TokenTree, ty::Ty, word::Word,
+};
 
 salsa::entity2! {
     entity Function in crate::Jar {
@@ -7,6 +9,9 @@ salsa::entity2! {
         effect: Effect,
         argument_tokens: TokenTree,
         code: Code,
+
+        /// Lines of the `///` comments before the function.
+        #[value ref] docs: Vec<Word>,
     }
 }
 
@@ -16,6 +21,15 @@ pub enum Effect {
     Async,
 }
 
+salsa::entity2! {
+    entity Parameter in crate::Jar {
+        #[id] name: Word,
+        name_span: Span,
+        mode: StorageMode,
+        ty: Option<Ty>,
+    }
+}
+
 salsa::entity2! {
     entity Variable in crate::Jar {
         #[id] name: Word,
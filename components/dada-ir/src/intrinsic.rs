@@ -0,0 +1,53 @@
+use crate::{func::Effect, word::Word};
+
+/// Functions that are built into the language rather than
+/// defined in Dada source.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Intrinsic {
+    /// `print(m: value)` -- returns a thunk that, when awaited,
+    /// writes `value` followed by a newline.
+    Print,
+
+    /// `spawn(task: thunk)` -- starts running `thunk` as a new task,
+    /// concurrently with the caller.
+    Spawn,
+}
+
+impl Intrinsic {
+    const ALL: &'static [Intrinsic] = &[Intrinsic::Print, Intrinsic::Spawn];
+
+    pub fn str(self) -> &'static str {
+        match self {
+            Intrinsic::Print => "print",
+            Intrinsic::Spawn => "spawn",
+        }
+    }
+
+    /// Names of the arguments that the intrinsic expects.
+    pub fn parameters(self) -> &'static [&'static str] {
+        match self {
+            Intrinsic::Print => &["m"],
+            Intrinsic::Spawn => &["task"],
+        }
+    }
+
+    /// Async intrinsics return a thunk when called; the others run immediately.
+    pub fn effect(self) -> Effect {
+        match self {
+            Intrinsic::Print => Effect::Async,
+            Intrinsic::Spawn => Effect::None,
+        }
+    }
+
+    /// Returns the intrinsic named `name`, if any.
+    pub fn from_word(db: &dyn crate::Db, name: Word) -> Option<Intrinsic> {
+        let name = name.as_str(db);
+        Self::ALL.iter().copied().find(|i| i.str() == name)
+    }
+}
+
+impl std::fmt::Display for Intrinsic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.str())
+    }
+}
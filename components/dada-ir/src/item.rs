@@ -1,4 +1,4 @@
-use crate::{class::Class, func::Function};
+use crate::{class::Class, func::Function, word::Word};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum Item {
@@ -6,6 +6,23 @@ pub enum Item {
     Class(Class),
 }
 
+impl Item {
+    pub fn name(self, db: &dyn crate::Db) -> Word {
+        match self {
+            Item::Function(f) => f.name(db),
+            Item::Class(c) => c.name(db),
+        }
+    }
+
+    /// Lines of the doc comments written before the item.
+    pub fn docs(self, db: &dyn crate::Db) -> &[Word] {
+        match self {
+            Item::Function(f) => f.docs(db),
+            Item::Class(c) => c.docs(db),
+        }
+    }
+}
+
 impl From<Function> for Item {
     fn from(value: Function) -> Self {
         Self::Function(value)
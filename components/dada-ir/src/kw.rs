@@ -53,10 +53,14 @@ define_keywords! {
     Atomic => "atomic",
     Fn => "fn",
     Async => "async",
+    Await => "await",
     If => "if",
     Else => "else",
     Loop => "loop",
     While => "while",
+    Break => "break",
+    Continue => "continue",
+    Return => "return",
 }
 
 #[salsa::memoized(in crate::Jar ref)]
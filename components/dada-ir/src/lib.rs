@@ -1,10 +1,15 @@
+pub mod arguments;
+pub mod cfg;
 pub mod class;
 pub mod code;
+pub mod definition;
 pub mod diagnostic;
 pub mod format_string;
 pub mod func;
+pub mod intrinsic;
 pub mod item;
 pub mod kw;
+pub mod number;
 pub mod op;
 pub mod span;
 pub mod storage_mode;
@@ -22,6 +27,7 @@ pub struct Jar(
     format_string::FormatString,
     format_string::FormatStringSection,
     func::Function,
+    func::Parameter,
     func::Variable,
     kw::keywords,
     op::binary_ops,
@@ -0,0 +1,107 @@
+//! Values of number literals like `22_000`, `0xFF`, `0b1010`, or `2.5e3`.
+
+/// The value of a number literal.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Number {
+    Integer(i64),
+    Float(Float),
+}
+
+/// A 64-bit float, stored as its bits so that it can be hashed and compared
+/// for equality (as is needed for anything stored in an [`Ast`](crate::code::Ast)).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Float(u64);
+
+impl Float {
+    pub fn new(value: f64) -> Self {
+        Float(value.to_bits())
+    }
+
+    pub fn value(self) -> f64 {
+        f64::from_bits(self.0)
+    }
+}
+
+impl std::fmt::Debug for Float {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.value())
+    }
+}
+
+impl Number {
+    /// Parses the text of a number literal, as produced by the lexer.
+    /// Underscores may be used anywhere after the first digit as separators.
+    pub fn parse(text: &str) -> Result<Number, NumberError> {
+        Self::parse_signed(text, false)
+    }
+
+    /// Parses the text of a number literal that is preceded by `-`, returning
+    /// the negated value. Unlike [`Number::parse`], this accepts `9223372036854775808`,
+    /// since `-9223372036854775808` fits in a 64-bit integer.
+    pub fn parse_negated(text: &str) -> Result<Number, NumberError> {
+        Self::parse_signed(text, true)
+    }
+
+    fn parse_signed(text: &str, negated: bool) -> Result<Number, NumberError> {
+        let sign = if negated { "-" } else { "" };
+        let digits: String = text.chars().filter(|&c| c != '_').collect();
+
+        let radix_digits = |prefix: &str, radix: u32| -> Option<Result<Number, NumberError>> {
+            let rest = digits.strip_prefix(prefix)?;
+            Some(if rest.is_empty() {
+                Err(NumberError::MissingDigits(radix))
+            } else if let Some(c) = rest.chars().find(|c| !c.is_digit(radix)) {
+                Err(NumberError::InvalidDigit(c, radix))
+            } else {
+                i64::from_str_radix(&format!("{sign}{rest}"), radix)
+                    .map(Number::Integer)
+                    .map_err(|_| NumberError::TooLarge)
+            })
+        };
+        if let Some(result) = radix_digits("0x", 16).or_else(|| radix_digits("0b", 2)) {
+            return result;
+        }
+
+        if digits.contains(&['.', 'e', 'E'][..]) {
+            let value: f64 = format!("{sign}{digits}")
+                .parse()
+                .map_err(|_| NumberError::InvalidFloat)?;
+            if value.is_infinite() {
+                return Err(NumberError::TooLarge);
+            }
+            return Ok(Number::Float(Float::new(value)));
+        }
+
+        format!("{sign}{digits}")
+            .parse()
+            .map(Number::Integer)
+            .map_err(|_| NumberError::TooLarge)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NumberError {
+    /// Does not fit in a 64-bit integer (or float).
+    TooLarge,
+
+    /// A digit that is not valid in the given radix.
+    InvalidDigit(char, u32),
+
+    /// A prefix like `0x` with no digits after it; carries the radix.
+    MissingDigits(u32),
+
+    InvalidFloat,
+}
+
+impl std::fmt::Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberError::TooLarge => write!(f, "number literal is too large"),
+            NumberError::InvalidDigit(c, radix) => {
+                write!(f, "invalid digit `{c}` in base {radix} literal")
+            }
+            NumberError::MissingDigits(radix) => write!(f, "expected base {radix} digits"),
+            NumberError::InvalidFloat => write!(f, "invalid float literal"),
+        }
+    }
+}
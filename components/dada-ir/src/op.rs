@@ -43,6 +43,11 @@ define_operators! {
     DividedByEqual => "/=",
     ColonEqual => ":=",
     EqualEqual => "==",
+    NotEqual => "!=",
+    LessEqual => "<=",
+    GreaterEqual => ">=",
+    AndAnd => "&&",
+    OrOr => "||",
 
     // 1-character ops
     Plus => "+",
@@ -52,6 +57,7 @@ define_operators! {
     Colon => ":",
     SemiColon => ";",
     Equal => "=",
+    Not => "!",
     LessThan => "<",
     GreaterThan => ">",
     LeftAngle => "<",
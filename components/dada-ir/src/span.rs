@@ -32,7 +32,7 @@ impl Span {
     pub fn start(self) -> Span {
         Span {
             start: self.start,
-            end: self.end,
+            end: self.start,
         }
     }
 
@@ -81,6 +81,14 @@ impl std::ops::Sub<Offset> for Offset {
     }
 }
 
+impl std::ops::Sub<u32> for Offset {
+    type Output = Offset;
+
+    fn sub(self, other: u32) -> Offset {
+        Offset(self.0 - other)
+    }
+}
+
 impl From<usize> for Offset {
     fn from(value: usize) -> Offset {
         assert!(value < std::u32::MAX as usize);
@@ -33,6 +33,12 @@ pub enum Token {
     /// Some whitespace (` `, `\n`, etc)
     Whitespace(char),
 
+    /// A `#` or `//` comment, up to (but not including) the end of the line
+    Comment(Word),
+
+    /// A `///` comment, documenting the item that follows
+    DocComment(Word),
+
     /// Some unclassifiable, non-whitespace char
     Unknown(char),
 }
@@ -44,7 +50,10 @@ impl Token {
             Token::Alphabetic(word)
             | Token::Number(word)
             | Token::Prefix(word)
-            | Token::StringLiteral(word) => word.as_str(db).len().try_into().unwrap(),
+            | Token::Comment(word)
+            | Token::DocComment(word) => word.as_str(db).len().try_into().unwrap(),
+            // +2 for the quotes
+            Token::StringLiteral(word) => word.len(db) + 2,
             Token::FormatString(f) => f.len(db),
             Token::Delimiter(ch) | Token::Op(ch) | Token::Whitespace(ch) | Token::Unknown(ch) => {
                 ch.len_utf8().try_into().unwrap()
@@ -1,3 +1,80 @@
+use crate::word::Word;
+
 #[salsa::interned(Ty in super::Jar)]
-#[derive(PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
-pub enum TyData {}
+#[derive(PartialEq, Eq, Clone, PartialOrd, Ord, Hash, Debug)]
+pub enum TyData {
+    /// A type written as a name, such as `String` or `Point`, along with
+    /// its type arguments, as in `Pair[String, Point]`. The name may also
+    /// be one of the type parameters of the enclosing class.
+    Named(Word, Vec<Ty>),
+
+    /// A type with a permission, such as `leased Point`. A type without one
+    /// accepts values with any permission.
+    Permission(Permission, Ty),
+}
+
+/// The permission in a type like `my Point`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Permission {
+    /// `my`: a uniquely owned value.
+    My,
+
+    /// `our`: a jointly owned value.
+    Our,
+
+    /// `shared`: a shared lease.
+    Shared,
+
+    /// `leased`: a unique lease.
+    Leased,
+}
+
+impl Permission {
+    pub fn str(self) -> &'static str {
+        match self {
+            Permission::My => "my",
+            Permission::Our => "our",
+            Permission::Shared => "shared",
+            Permission::Leased => "leased",
+        }
+    }
+
+    /// True if a value with permission `self` can be used where `expected`
+    /// is required: owned values can also be leased or shared, and unique
+    /// ones can also be shared.
+    pub fn satisfies(self, expected: Permission) -> bool {
+        use Permission::*;
+        matches!(
+            (self, expected),
+            (My, _) | (Our, Our | Shared) | (Leased, Leased | Shared) | (Shared, Shared)
+        )
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.str())
+    }
+}
+
+impl Ty {
+    /// Renders the type as it would be written in Dada source.
+    pub fn display(self, db: &dyn crate::Db) -> String {
+        match self.data(db) {
+            TyData::Named(name, arguments) if arguments.is_empty() => name.as_str(db).to_string(),
+            TyData::Named(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|a| a.display(db)).collect();
+                format!("{}[{}]", name.as_str(db), arguments.join(", "))
+            }
+            TyData::Permission(permission, ty) => format!("{permission} {}", ty.display(db)),
+        }
+    }
+
+    /// Splits the type into its permission (if any) and the type without it.
+    pub fn strip_permission(self, db: &dyn crate::Db) -> (Option<Permission>, Ty) {
+        match *self.data(db) {
+            TyData::Permission(permission, ty) => (Some(permission), ty),
+            TyData::Named(..) => (None, self),
+        }
+    }
+}
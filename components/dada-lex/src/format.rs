@@ -0,0 +1,65 @@
+use dada_ir::{diagnostic::Diagnostic, span::Span, word::Word};
+
+/// Renders `diagnostic` for display on a terminal, along with the
+/// line of source that it refers to and any labels:
+///
+/// ```text
+/// foo.dada:4:5: error: cannot use `p` while it is leased
+///   |     p.x += 1
+///   |     ^
+/// foo.dada:3:9: note: `p` was leased here
+///   |     q = p.lease
+///   |         ^^^^^^^
+/// ```
+pub fn format_diagnostic(db: &dyn crate::Db, diagnostic: &Diagnostic) -> String {
+    let filename = diagnostic.filename;
+    let mut output = format_span(db, filename, diagnostic.span, "error", &diagnostic.message);
+    for label in &diagnostic.labels {
+        output.push('\n');
+        output.push_str(&format_span(
+            db,
+            filename,
+            label.span,
+            "note",
+            &label.message,
+        ));
+    }
+    output
+}
+
+fn format_span(
+    db: &dyn crate::Db,
+    filename: Word,
+    span: Span,
+    kind: &str,
+    message: &str,
+) -> String {
+    let start = crate::line_column(db, filename, span.start);
+    let end = crate::line_column(db, filename, span.end);
+
+    let mut output = format!(
+        "{}:{}:{}: {kind}: {message}",
+        filename.as_str(db),
+        start.line,
+        start.column,
+    );
+
+    let source_text = dada_manifest::source_text(db, filename);
+    if let Some(line_text) = source_text.lines().nth(start.line as usize - 1) {
+        // Underline to the end of the span, or the end of the line if the span
+        // covers multiple lines; always underline at least one character.
+        let end_column = if end.line == start.line {
+            end.column
+        } else {
+            line_text.len() as u32 + 1
+        };
+        let underline = end_column.saturating_sub(start.column).max(1);
+        output.push_str(&format!(
+            "\n  | {line_text}\n  | {}{}",
+            " ".repeat(start.column as usize - 1),
+            "^".repeat(underline as usize),
+        ));
+    }
+
+    output
+}
@@ -1,5 +1,6 @@
 use dada_ir::diagnostic::{Diagnostic, Diagnostics};
 use dada_ir::format_string::{FormatStringData, FormatStringSection, FormatStringSectionData};
+use dada_ir::number::{Number, NumberError};
 use dada_ir::span::{Offset, Span};
 use dada_ir::token::Token;
 use dada_ir::token_tree::TokenTree;
@@ -14,8 +15,10 @@ pub fn lex_file(db: &dyn crate::Db, filename: Word) -> TokenTree {
         filename,
         chars,
         file_len: source_text.len(),
+        line_indent: 0,
+        at_line_start: true,
     };
-    lexer.lex_tokens(None)
+    lexer.lex_tokens(None, None)
 }
 
 #[track_caller]
@@ -30,7 +33,7 @@ pub fn closing_delimiter(ch: char) -> char {
 
 macro_rules! op {
     () => {
-        '+' | '-' | '/' | '*' | '>' | '<' | '&' | '|' | '.' | ',' | ':' | ';'
+        '+' | '-' | '/' | '*' | '>' | '<' | '&' | '|' | '.' | ',' | ':' | ';' | '=' | '!'
     };
 }
 
@@ -42,33 +45,75 @@ where
     filename: Word,
     chars: &'me mut Peekable<I>,
     file_len: usize,
+
+    /// Number of spaces and tabs at the start of the current line.
+    line_indent: usize,
+
+    /// True until the first non-whitespace character of the current line is reached.
+    at_line_start: bool,
 }
 
 impl<'me, I> Lexer<'me, I>
 where
-    I: Iterator<Item = (usize, char)>,
+    I: Iterator<Item = (usize, char)> + Clone,
 {
-    fn lex_tokens(&mut self, end_ch: Option<char>) -> TokenTree {
+    /// Lexes tokens until `end_ch` (which is not consumed) or the end of the file.
+    ///
+    /// If `opener_indent` is given, this is the contents of a delimiter opened on a line
+    /// with that indentation. Lexing then also stops, so that the delimiter is reported
+    /// as unclosed, at a line indented less than that, or at a line indented the same
+    /// that starts a new item. This keeps one missing `}` from swallowing every later item.
+    /// Lines that start with a comment never stop it.
+    fn lex_tokens(&mut self, end_ch: Option<char>, opener_indent: Option<usize>) -> TokenTree {
         let mut tokens = vec![];
-        let mut start_pos = self.file_len;
-        let mut end_pos = self.file_len;
+        let start_pos = self.peek_offset();
         while let Some((pos, ch)) = self.chars.peek().cloned() {
-            start_pos = start_pos.min(pos);
-            end_pos = end_pos.max(pos);
-
             if Some(ch) == end_ch {
                 break;
             }
 
+            match ch {
+                '\n' => {
+                    self.at_line_start = true;
+                    self.line_indent = 0;
+                }
+                ' ' | '\t' if self.at_line_start => self.line_indent += 1,
+                _ if self.at_line_start => {
+                    self.at_line_start = false;
+                    // Comments may be indented any way they like.
+                    let comment = ch == '#' || (ch == '/' && self.peek_nth(1) == Some('/'));
+                    if let Some(opener_indent) = opener_indent.filter(|_| !comment) {
+                        if self.line_indent < opener_indent
+                            || (self.line_indent == opener_indent && self.starts_item())
+                        {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
             self.chars.next();
 
             match ch {
                 '(' | '[' | '{' => {
                     tokens.push(Token::Delimiter(ch));
-                    let tree = self.lex_tokens(Some(closing_delimiter(ch)));
+                    let closing = closing_delimiter(ch);
+                    let tree = self.lex_tokens(Some(closing), Some(self.line_indent));
                     tokens.push(Token::Tree(tree));
+                    if self.peek_nth(0) == Some(closing) {
+                        self.chars.next();
+                        self.at_line_start = false;
+                        tokens.push(Token::Delimiter(closing));
+                    } else {
+                        self.report(
+                            Span::from(pos, pos + 1),
+                            format!("unclosed `{ch}` opened here"),
+                        );
+                    }
                 }
                 ')' | ']' | '}' => {
+                    self.report(Span::from(pos, pos + 1), format!("unexpected `{ch}`"));
                     tokens.push(Token::Delimiter(ch));
                 }
                 'a'..='z' | 'A'..='Z' | '_' => {
@@ -83,21 +128,37 @@ where
                         .map(|&(_, ch)| matches!(ch, '"' | '\''))
                         .unwrap_or(false);
 
-                    if is_prefix {
+                    if is_prefix && self.peek_nth(0) == Some('"') {
+                        let kind = self.string_kind(pos, text);
+                        self.chars.next();
+                        if kind == StringKind::Byte {
+                            // The parser turns the prefix and the string into a list of bytes.
+                            tokens.push(Token::Prefix(text));
+                            let quote = pos + text.as_str(self.db).len();
+                            tokens.push(self.string_literal(Offset::from(quote), kind));
+                        } else {
+                            tokens.push(self.string_literal(Offset::from(pos), kind));
+                        }
+                    } else if is_prefix {
                         tokens.push(Token::Prefix(text));
                     } else {
                         tokens.push(Token::Alphabetic(text));
                     }
                 }
                 '0'..='9' => {
-                    let text = self.accumulate(ch, |c| matches!(c, '0'..='9' | '_'));
-                    tokens.push(Token::Number(text));
+                    tokens.push(self.number(pos, ch));
+                }
+                '#' => {
+                    tokens.push(self.comment(pos, ch, end_ch.is_some()));
+                }
+                '/' if self.peek_nth(0) == Some('/') => {
+                    tokens.push(self.comment(pos, ch, end_ch.is_some()));
                 }
                 op!() => {
                     tokens.push(Token::Op(ch));
                 }
                 '"' => {
-                    tokens.push(self.string_literal(Offset::from(pos)));
+                    tokens.push(self.string_literal(Offset::from(pos), StringKind::Format));
                 }
                 _ => {
                     if !ch.is_whitespace() {
@@ -109,6 +170,7 @@ where
             }
         }
 
+        let end_pos = self.peek_offset();
         TokenTree::new(
             self.db,
             self.filename,
@@ -117,6 +179,43 @@ where
         )
     }
 
+    /// True if the upcoming characters begin a class or function definition.
+    fn starts_item(&self) -> bool {
+        starts_item(self.chars.clone())
+    }
+
+    /// True if the first line after this one that is neither blank nor
+    /// a comment begins a class or function definition.
+    fn item_follows(&self) -> bool {
+        let mut chars = self.chars.clone();
+        loop {
+            while let Some(&(_, ch)) = chars.peek() {
+                if !ch.is_whitespace() {
+                    break;
+                }
+                chars.next();
+            }
+            let mut ahead = chars.clone().map(|(_, ch)| ch);
+            match (ahead.next(), ahead.next()) {
+                (Some('#'), _) | (Some('/'), Some('/')) => {
+                    // Skip the rest of the comment's line.
+                    chars.by_ref().find(|&(_, ch)| ch == '\n');
+                }
+                _ => return starts_item(chars),
+            }
+        }
+    }
+
+    /// Offset of the next character to be consumed (or the end of the file).
+    fn peek_offset(&mut self) -> Offset {
+        let offset = self
+            .chars
+            .peek()
+            .map(|&(offset, _)| offset)
+            .unwrap_or(self.file_len);
+        Offset::from(offset)
+    }
+
     /// Accumulate `ch0` and following characters while `matches` returns true
     /// into an interned string.
     fn accumulate(&mut self, ch0: char, matches: impl Fn(char) -> bool) -> Word {
@@ -133,21 +232,153 @@ where
         Word::from(self.db, string)
     }
 
-    /// Invoked after consuming a `"`
-    fn string_literal(&mut self, start: Offset) -> Token {
+    /// Returns the character `n` places after the next one, without consuming anything.
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n).map(|(_, ch)| ch)
+    }
+
+    /// Consumes characters while `matches` returns true, appending them to `text`.
+    fn accumulate_into(&mut self, text: &mut String, matches: impl Fn(char) -> bool) {
+        while let Some(ch) = self.peek_nth(0) {
+            if !matches(ch) {
+                break;
+            }
+            text.push(ch);
+            self.chars.next();
+        }
+    }
+
+    /// Invoked after consuming `ch0`, which starts a comment at `start` that runs to the
+    /// end of the line. `nested` is true if the comment is inside a delimiter.
+    fn comment(&mut self, start: usize, ch0: char, nested: bool) -> Token {
+        let mut text = String::new();
+        text.push(ch0);
+        self.accumulate_into(&mut text, |ch| ch != '\n');
+
+        // As in Rust, `////...` is an ordinary comment.
+        if text.starts_with("///") && !text.starts_with("////") {
+            // Only items can be documented, and they are never nested.
+            if nested || !self.item_follows() {
+                self.report(
+                    Span::from(start, start + text.len()),
+                    "doc comment is not followed by a function or class",
+                );
+            }
+            Token::DocComment(Word::from(self.db, text))
+        } else {
+            Token::Comment(Word::from(self.db, text))
+        }
+    }
+
+    /// Invoked after consuming `ch0`, the first digit of a number starting at `start`.
+    /// Accepts `22_000`, `0xFF`, `0b1010`, `2.5` and `1e-3`; reports an error if the
+    /// literal is malformed. Whether it is too large depends on whether it is negated,
+    /// so that is left to the parser.
+    fn number(&mut self, start: usize, ch0: char) -> Token {
+        let is_digit = |ch: char| matches!(ch, '0'..='9' | '_');
+        let mut text = String::new();
+        text.push(ch0);
+
+        if ch0 == '0' && matches!(self.peek_nth(0), Some('x' | 'b')) {
+            // Take every alphanumeric character, so that bad digits are reported below.
+            self.accumulate_into(&mut text, |ch| ch.is_ascii_alphanumeric() || ch == '_');
+        } else {
+            self.accumulate_into(&mut text, is_digit);
+
+            // A `.` is a decimal point only if a digit follows, so that `22.share` still works.
+            if self.peek_nth(0) == Some('.') && matches!(self.peek_nth(1), Some('0'..='9')) {
+                self.accumulate_into(&mut text, |ch| ch == '.');
+                self.accumulate_into(&mut text, is_digit);
+            }
+
+            let has_exponent = match (self.peek_nth(0), self.peek_nth(1), self.peek_nth(2)) {
+                (Some('e' | 'E'), Some('0'..='9'), _) => true,
+                (Some('e' | 'E'), Some('+' | '-'), Some('0'..='9')) => true,
+                _ => false,
+            };
+            if has_exponent {
+                for _ in 0..2 {
+                    text.push(self.chars.next().unwrap().1);
+                }
+                self.accumulate_into(&mut text, is_digit);
+            }
+        }
+
+        match Number::parse(&text) {
+            Ok(_) | Err(NumberError::TooLarge) => {}
+            Err(error) => self.report(Span::from(start, start + text.len()), error),
+        }
+
+        Token::Number(Word::from(self.db, text))
+    }
+
+    fn report(&self, span: Span, message: impl ToString) {
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                filename: self.filename,
+                span,
+                message: message.to_string(),
+                labels: vec![],
+            },
+        );
+    }
+
+    /// Interprets `prefix`, which starts at `start` and is immediately followed by a `"`.
+    fn string_kind(&self, start: usize, prefix: Word) -> StringKind {
+        match prefix.as_str(self.db) {
+            "r" => StringKind::Raw,
+            "b" => StringKind::Byte,
+            other => {
+                self.report(
+                    Span::from(start, start + other.len()),
+                    format!("unknown string prefix `{other}` (expected `r` or `b`)"),
+                );
+                StringKind::Format
+            }
+        }
+    }
+
+    /// Invoked after consuming the opening `"` of a string literal that starts
+    /// at `start` (including any prefix, except that of a byte string).
+    fn string_literal(&mut self, start: Offset, kind: StringKind) -> Token {
+        let content_start = self.peek_offset();
+
+        // If the string is never closed, end it at the end of its first line
+        // rather than swallowing the code that follows.
+        let closed = self.has_closing_quote(kind);
+
         let mut buffer = StringFormatBuffer::new(self.db);
-        let mut end = start;
-        while let Some((ch_offset, ch)) = self.chars.next() {
-            let ch_offset = Offset::from(ch_offset);
-            end = end.max(ch_offset);
+        let mut terminated = false;
+        while let Some(&(ch_offset, ch)) = self.chars.peek() {
+            if ch == '\n' && !closed {
+                break;
+            }
+
+            self.chars.next();
 
             if ch == '"' {
+                terminated = true;
                 break;
             }
 
-            if ch == '{' {
+            if ch == '\\' && kind != StringKind::Raw {
+                if let Some(ch) = self.escape(ch_offset, kind) {
+                    buffer.push_char(ch);
+                }
+                continue;
+            }
+
+            if kind == StringKind::Byte && !ch.is_ascii() {
+                self.report(
+                    Span::from(ch_offset, ch_offset + ch.len_utf8()),
+                    "byte strings can only contain ASCII characters",
+                );
+            }
+
+            if ch == '{' && kind == StringKind::Format {
                 // Format string! Grab a token tree.
-                let tree = self.lex_tokens(Some('}'));
+                let tree = self.lex_tokens(Some('}'), None);
                 buffer.push_tree(tree);
 
                 if let Some(&(_, '}')) = self.chars.peek() {
@@ -168,6 +399,7 @@ where
                                 end,
                             },
                             message: format!("format string missing closing brace in code section"),
+                            labels: vec![],
                         },
                     );
                     break;
@@ -179,10 +411,23 @@ where
         }
 
         buffer.flush_text();
+        let end = self.peek_offset();
 
-        if buffer.sections.len() == 1 {
+        if !terminated {
+            let quote = content_start - 1_u32;
+            self.report(
+                Span::from(quote, quote + 1_u32),
+                "unterminated string starting here",
+            );
+        }
+
+        // A `StringLiteral` token's length is inferred from its text, so
+        // we can only use one if the source was exactly `"text"`.
+        if terminated && buffer.sections.len() == 1 {
             if let FormatStringSectionData::Text(word) = buffer.sections[0].data(self.db) {
-                return Token::StringLiteral(*word);
+                if word.len(self.db) + 2 == end - start {
+                    return Token::StringLiteral(*word);
+                }
             }
         }
 
@@ -193,6 +438,151 @@ where
         .intern(self.db);
         Token::FormatString(format_string)
     }
+
+    /// True if the string whose opening quote was just consumed is closed, skipping
+    /// over escapes and any `{...}` sections. A string may only continue onto lines
+    /// that are indented more than the line it starts on (blank lines aside), so the
+    /// search stops at the first line that is not.
+    fn has_closing_quote(&self, kind: StringKind) -> bool {
+        let mut chars = self.chars.clone();
+        let mut depth = 0;
+        while let Some((_, ch)) = chars.next() {
+            match ch {
+                '\\' if kind != StringKind::Raw => {
+                    chars.next();
+                }
+                '\n' => {
+                    let mut indent = 0;
+                    while let Some(&(_, ' ' | '\t')) = chars.peek() {
+                        chars.next();
+                        indent += 1;
+                    }
+                    let blank = matches!(chars.peek(), Some(&(_, '\n')));
+                    if indent <= self.line_indent && !blank {
+                        return false;
+                    }
+                }
+                '{' if kind == StringKind::Format => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                '"' if depth == 0 => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Invoked after consuming the `\` at `start` in a string of the given kind.
+    /// Returns the character that the escape sequence stands for, or `None`
+    /// (after reporting an error) if it is invalid.
+    fn escape(&mut self, start: usize, kind: StringKind) -> Option<char> {
+        let (_, ch) = self.chars.next()?;
+        let escaped = match ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' | '"' | '\'' | '{' | '}' => ch,
+            'x' => {
+                let digits = self.hex_digits(2);
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => byte as char,
+                    _ => {
+                        self.report(
+                            Span::from(Offset::from(start), self.peek_offset()),
+                            "expected `\\x` followed by two hex digits, at most `7F`",
+                        );
+                        return None;
+                    }
+                }
+            }
+            'u' => {
+                let value = match self.peek_nth(0) {
+                    Some('{') => {
+                        self.chars.next();
+                        let digits = self.hex_digits(6);
+                        let closed = self.peek_nth(0) == Some('}');
+                        if closed {
+                            self.chars.next();
+                        }
+                        u32::from_str_radix(&digits, 16).ok().filter(|_| closed)
+                    }
+                    _ => None,
+                };
+                let span = Span::from(Offset::from(start), self.peek_offset());
+                match value {
+                    None => {
+                        self.report(
+                            span,
+                            "invalid unicode escape; expected `\\u{...}` with 1 to 6 hex digits",
+                        );
+                        return None;
+                    }
+                    Some(_) if kind == StringKind::Byte => {
+                        self.report(span, "unicode escapes are not allowed in byte strings");
+                        return None;
+                    }
+                    Some(value) => match char::from_u32(value) {
+                        Some(ch) => ch,
+                        None => {
+                            self.report(
+                                span,
+                                format!("`\\u{{{value:X}}}` is not a valid unicode character"),
+                            );
+                            return None;
+                        }
+                    },
+                }
+            }
+            _ => {
+                self.report(
+                    Span::from(Offset::from(start), self.peek_offset()),
+                    format!("invalid escape sequence `\\{ch}`"),
+                );
+                return None;
+            }
+        };
+        Some(escaped)
+    }
+
+    /// Consumes up to `max` hex digits.
+    fn hex_digits(&mut self, max: usize) -> String {
+        let mut digits = String::new();
+        while digits.len() < max {
+            match self.peek_nth(0) {
+                Some(ch) if ch.is_ascii_hexdigit() => {
+                    digits.push(ch);
+                    self.chars.next();
+                }
+                _ => break,
+            }
+        }
+        digits
+    }
+}
+
+/// True if `chars` begin with the keyword that starts a class or function definition.
+fn starts_item(chars: impl Iterator<Item = (usize, char)>) -> bool {
+    let word: String = chars
+        .map(|(_, ch)| ch)
+        .take_while(|&ch| ch.is_ascii_alphanumeric() || ch == '_')
+        .collect();
+    matches!(&word[..], "class" | "fn" | "async")
+}
+
+/// How the contents of a string literal are read, depending on its prefix.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum StringKind {
+    /// `"..."`: escapes like `\n` are processed and `{expr}` is interpolated;
+    /// `\{` and `\}` produce literal braces.
+    Format,
+
+    /// `r"..."`: the text is taken exactly as written, without escapes or interpolation.
+    Raw,
+
+    /// `b"..."`: escapes are processed but nothing is interpolated, and every
+    /// character must be ASCII, so that each one is a single byte. The value
+    /// is a list of the bytes, e.g. `b"hi"` is `[104, 105]`.
+    Byte,
 }
 
 struct StringFormatBuffer<'me> {
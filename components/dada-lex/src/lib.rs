@@ -1,6 +1,7 @@
 #![feature(trait_upcasting)]
 #![allow(incomplete_features)]
 
+mod format;
 mod lex;
 mod lines;
 
@@ -19,6 +20,8 @@ where
     }
 }
 
+pub use format::format_diagnostic;
 pub use lex::closing_delimiter;
 pub use lex::lex_file;
 pub use lines::line_column;
+pub use lines::offset;
@@ -14,35 +14,61 @@ pub struct LineTable {
 /// Converts a character index `position` into a (1-based) line and column tuple.
 pub fn line_column(db: &dyn crate::Db, filename: Word, position: Offset) -> LineColumn {
     let table = line_table(db, filename);
-    match table.line_endings.binary_search(&position) {
-        Ok(line) => LineColumn {
-            line: line as u32 + 1,
-            column: 1,
-        },
-        Err(0) => LineColumn {
-            line: 1,
-            column: (position + 1_u32).into(),
-        },
-        Err(line) => {
-            let end_previous_line = table.line_endings[line - 1];
-            LineColumn {
-                line: line as u32 + 1,
-                column: position - end_previous_line + 1,
-            }
-        }
+
+    // Number of lines that end before `position`; the `\n` itself
+    // is considered part of the line that it ends.
+    let line = match table.line_endings.binary_search(&position) {
+        Ok(line) | Err(line) => line,
+    };
+
+    let line_start = if line == 0 {
+        0
+    } else {
+        let end_previous_line: u32 = table.line_endings[line - 1].into();
+        end_previous_line + 1
+    };
+    let position: u32 = position.into();
+
+    LineColumn {
+        line: line as u32 + 1,
+        column: position - line_start + 1,
     }
 }
 
+/// Converts a (1-based) line and column into a character index; the inverse of [`line_column`].
+/// Positions past the end of a line are clamped to its end (the `\n`), and positions past the
+/// end of the file are clamped to the end of the file.
+pub fn offset(db: &dyn crate::Db, filename: Word, position: LineColumn) -> Offset {
+    let table = line_table(db, filename);
+    let line = position.line.max(1) as usize - 1;
+
+    let line_start = if line == 0 {
+        0
+    } else {
+        match table.line_endings.get(line - 1) {
+            Some(&end_previous_line) => u32::from(end_previous_line) + 1,
+            None => return Offset::from(dada_manifest::source_text(db, filename).len()),
+        }
+    };
+    let line_end = match table.line_endings.get(line) {
+        Some(&end) => u32::from(end),
+        None => dada_manifest::source_text(db, filename).len() as u32,
+    };
+
+    let column = position.column.max(1) - 1;
+    Offset::from((line_start + column).min(line_end))
+}
+
 #[salsa::memoized(in crate::Jar ref)]
 fn line_table(db: &dyn crate::Db, filename: Word) -> LineTable {
     let source_text = dada_manifest::source_text(db, filename);
-    let mut p: usize = 0;
     let mut table = LineTable {
         line_endings: vec![],
     };
-    for line in source_text.lines() {
-        p += line.len();
-        table.line_endings.push(Offset::from(p));
+    for (offset, ch) in source_text.char_indices() {
+        if ch == '\n' {
+            table.line_endings.push(Offset::from(offset));
+        }
     }
     table
 }
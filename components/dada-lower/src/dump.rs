@@ -0,0 +1,250 @@
+use dada_ir::{
+    cfg::{
+        BasicBlock, Cfg, Local, LocalOrigin, Place, Projection, Rvalue, Statement, StatementKind,
+        Terminator,
+    },
+    code::CaptureMode,
+    definition::Definition,
+    func::Function,
+    number::Number,
+    storage_mode::StorageMode,
+};
+
+/// Renders the control-flow graph of `function` as text, listing its locals
+/// and then each of its basic blocks:
+///
+/// ```text
+/// fn main {
+///     let _0: return;
+///     let _1: var p;
+///
+///     bb0: {
+///         _1 = 22;
+///         _0 = ();
+///         drop _1;
+///         return;
+///     }
+/// }
+/// ```
+pub fn dump_cfg(db: &dyn crate::Db, function: Function) -> String {
+    let cfg = crate::lower_function(db, function);
+    let printer = Printer { db, cfg };
+
+    let mut lines = vec![format!("fn {} {{", function.name(db).as_str(db))];
+    for local in (0..cfg.locals.len()).map(Local::from) {
+        lines.push(format!(
+            "    let {}: {};",
+            printer.local(local),
+            printer.local_description(local)
+        ));
+    }
+    for block in (0..cfg.blocks.len()).map(BasicBlock::from) {
+        lines.push(String::new());
+        lines.push(format!("    {}: {{", printer.block(block)));
+        for statement in &cfg.blocks[block].statements {
+            lines.push(format!("        {};", printer.statement(statement)));
+        }
+        lines.push(format!(
+            "        {};",
+            printer.terminator(&cfg.blocks[block].terminator)
+        ));
+        lines.push("    }".to_string());
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// Renders the control-flow graph of `function` as Graphviz `dot` input,
+/// with one node per basic block.
+pub fn dump_cfg_graphviz(db: &dyn crate::Db, function: Function) -> String {
+    let cfg = crate::lower_function(db, function);
+    let printer = Printer { db, cfg };
+
+    let mut lines = vec![
+        format!(
+            "digraph \"{}\" {{",
+            dot_escape(function.name(db).as_str(db))
+        ),
+        "  node [shape = box, fontname = monospace];".to_string(),
+    ];
+    for block in (0..cfg.blocks.len()).map(BasicBlock::from) {
+        let data = &cfg.blocks[block];
+        let mut label = format!("{}:\\l", printer.block(block));
+        for statement in &data.statements {
+            label.push_str(&dot_escape(&printer.statement(statement)));
+            label.push_str("\\l");
+        }
+        label.push_str(&dot_escape(&printer.terminator(&data.terminator)));
+        label.push_str("\\l");
+        lines.push(format!(
+            "  {} [label = \"{}\"];",
+            printer.block(block),
+            label
+        ));
+
+        match data.terminator {
+            Terminator::Goto(target) => {
+                lines.push(format!(
+                    "  {} -> {};",
+                    printer.block(block),
+                    printer.block(target)
+                ));
+            }
+            Terminator::If(_, if_true, if_false) => {
+                for (target, label) in [(if_true, "true"), (if_false, "false")] {
+                    lines.push(format!(
+                        "  {} -> {} [label = \"{label}\"];",
+                        printer.block(block),
+                        printer.block(target)
+                    ));
+                }
+            }
+            Terminator::Return | Terminator::Error => {}
+        }
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+struct Printer<'me> {
+    db: &'me dyn crate::Db,
+    cfg: &'me Cfg,
+}
+
+impl Printer<'_> {
+    fn local(&self, local: Local) -> String {
+        format!("_{}", usize::from(local))
+    }
+
+    fn block(&self, block: BasicBlock) -> String {
+        format!("bb{}", usize::from(block))
+    }
+
+    fn local_description(&self, local: Local) -> String {
+        let data = &self.cfg.locals[local];
+        let mode = match data.mode {
+            StorageMode::Shared => "",
+            StorageMode::Var => "var ",
+            StorageMode::Atomic => "atomic ",
+        };
+        let name = data.name.map(|name| name.as_str(self.db)).unwrap_or("");
+        match data.origin {
+            LocalOrigin::Parameter(_) => format!("parameter {mode}{name}"),
+            LocalOrigin::Var(_) => format!("{mode}{name}"),
+            LocalOrigin::Temporary(_) => "temporary".to_string(),
+            LocalOrigin::Return => "return".to_string(),
+        }
+    }
+
+    fn locals(&self, locals: &[Local]) -> String {
+        let locals: Vec<String> = locals.iter().map(|&l| self.local(l)).collect();
+        locals.join(", ")
+    }
+
+    fn place(&self, place: &Place) -> String {
+        let mut output = self.local(place.local);
+        for projection in &place.projections {
+            match *projection {
+                Projection::Field(field) => {
+                    output.push('.');
+                    output.push_str(field.as_str(self.db));
+                }
+                Projection::Index(index) => {
+                    output.push_str(&format!("[{}]", self.local(index)));
+                }
+            }
+        }
+        output
+    }
+
+    fn statement(&self, statement: &Statement) -> String {
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                format!("{} = {}", self.place(place), self.rvalue(rvalue))
+            }
+            StatementKind::Drop(local) => format!("drop {}", self.local(*local)),
+            StatementKind::StartAtomic => "start atomic".to_string(),
+            StatementKind::EndAtomic => "end atomic".to_string(),
+        }
+    }
+
+    fn rvalue(&self, rvalue: &Rvalue) -> String {
+        match rvalue {
+            Rvalue::Give(place) => format!("give {}", self.place(place)),
+            Rvalue::Lease(place) => format!("lease {}", self.place(place)),
+            Rvalue::Share(place) => format!("share {}", self.place(place)),
+            Rvalue::Unit => "()".to_string(),
+            Rvalue::Number(Number::Integer(i)) => i.to_string(),
+            Rvalue::Number(Number::Float(f)) => format!("{f:?}"),
+            Rvalue::String(word) => format!("{:?}", word.as_str(self.db)),
+            Rvalue::Item(Definition::Class(class)) => class.name(self.db).as_str(self.db).into(),
+            Rvalue::Item(Definition::Function(function)) => {
+                function.name(self.db).as_str(self.db).into()
+            }
+            Rvalue::Item(Definition::Intrinsic(intrinsic)) => intrinsic.to_string(),
+            Rvalue::Item(Definition::Local(_) | Definition::Parameter(_)) => {
+                unreachable!("variables are not items")
+            }
+            Rvalue::Concatenate(places) => {
+                let places: Vec<String> = places.iter().map(|place| self.place(place)).collect();
+                format!("concatenate({})", places.join(", "))
+            }
+            Rvalue::Tuple(locals) if locals.len() == 1 => format!("({},)", self.locals(locals)),
+            Rvalue::Tuple(locals) => format!("({})", self.locals(locals)),
+            Rvalue::List(locals) => format!("[{}]", self.locals(locals)),
+            Rvalue::Call(callee, arguments) => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|&(name, argument)| {
+                        format!("{}: {}", name.as_str(self.db), self.local(argument))
+                    })
+                    .collect();
+                format!("call {}({})", self.local(*callee), arguments.join(", "))
+            }
+            Rvalue::Await(local) => format!("await {}", self.local(*local)),
+            Rvalue::Unary(op, local) => format!("{op}{}", self.local(*local)),
+            Rvalue::Op(lhs, op, rhs) => format!("{} {op} {}", self.local(*lhs), self.local(*rhs)),
+            Rvalue::Closure(mode, _, captures) => {
+                let mode = match mode {
+                    CaptureMode::Give => "give",
+                    CaptureMode::Lease => "lease",
+                    CaptureMode::Share => "share",
+                };
+                let captures: Vec<String> = captures
+                    .iter()
+                    .map(|&(name, local)| {
+                        format!("{}: {}", name.as_str(self.db), self.local(local))
+                    })
+                    .collect();
+                format!("{mode} closure {{{}}}", captures.join(", "))
+            }
+            Rvalue::Error => "error".to_string(),
+        }
+    }
+
+    fn terminator(&self, terminator: &Terminator) -> String {
+        match *terminator {
+            Terminator::Goto(target) => format!("goto {}", self.block(target)),
+            Terminator::If(condition, if_true, if_false) => format!(
+                "if {} then {} else {}",
+                self.local(condition),
+                self.block(if_true),
+                self.block(if_false)
+            ),
+            Terminator::Return => "return".to_string(),
+            Terminator::Error => "error".to_string(),
+        }
+    }
+}
+
+/// Escapes `text` for use within a quoted Graphviz label.
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if matches!(ch, '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
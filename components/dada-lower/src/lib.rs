@@ -0,0 +1,14 @@
+#![allow(incomplete_features)]
+#![feature(trait_upcasting)]
+
+mod dump;
+mod lower;
+
+#[salsa::jar(Db)]
+pub struct Jar(lower::lower_function);
+
+pub trait Db: salsa::DbWithJar<Jar> + dada_ir::Db + dada_parse::Db + dada_resolve::Db {}
+impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_ir::Db + dada_parse::Db + dada_resolve::Db {}
+
+pub use dump::{dump_cfg, dump_cfg_graphviz};
+pub use lower::lower_function;
@@ -0,0 +1,620 @@
+use dada_collections::{IndexVec, Map};
+use dada_ir::{
+    cfg::{
+        BasicBlock, BasicBlockData, Cfg, Local, LocalData, LocalOrigin, Origin, Place, Projection,
+        Rvalue, Statement, StatementKind, Terminator,
+    },
+    code::{Ast, Block, CaptureMode, Expr, ExprData},
+    definition::{Definition, Resolutions},
+    func::Function,
+    op::Op,
+    storage_mode::StorageMode,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Lowers the body of `function` into a control-flow graph.
+///
+/// Values are computed in the same order as the interpreter computes them,
+/// each intermediate value in its own temporary. Variables are dropped in
+/// declaration order at the end of their block, or when a `break`,
+/// `continue` or `return` leaves it. Closure bodies are not lowered; a
+/// closure appears only as the values that it captures.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn lower_function(db: &dyn crate::Db, function: Function) -> Cfg {
+    let ast = function.ast(db);
+    let mut lower = Lower {
+        ast,
+        resolutions: dada_resolve::resolve_names(db, function),
+        locals: IndexVec::new(),
+        blocks: IndexVec::new(),
+        current: BasicBlock::from(0),
+        variables: Map::default(),
+        scopes: vec![],
+        loops: vec![],
+        return_local: Local::from(0),
+        atomic_scopes: vec![],
+    };
+
+    let start = lower.new_block();
+    let return_local = lower.new_local(None, StorageMode::Var, LocalOrigin::Return);
+    lower.return_local = return_local;
+
+    // The body shares a scope with the parameters, as when it is executed.
+    let mut parameters = vec![];
+    for &parameter in function.parameters(db) {
+        let local = lower.new_local(
+            Some(parameter.name(db)),
+            parameter.mode(db),
+            LocalOrigin::Parameter(parameter),
+        );
+        lower
+            .variables
+            .insert(Definition::Parameter(parameter), local);
+        parameters.push(local);
+    }
+    lower.scopes.push(parameters);
+    lower.lower_block_contents(ast.block, Place::from(return_local));
+    lower.pop_scope(Origin::Block(ast.block));
+    lower.terminate(Terminator::Return);
+
+    Cfg {
+        locals: lower.locals,
+        blocks: lower.blocks,
+        start,
+        return_local,
+    }
+}
+
+struct Lower<'me> {
+    ast: &'me Ast,
+    resolutions: &'me Resolutions,
+
+    locals: IndexVec<Local, LocalData>,
+    blocks: IndexVec<BasicBlock, BasicBlockData>,
+
+    /// The block that statements are currently added to.
+    current: BasicBlock,
+
+    /// The local for each variable and parameter lowered so far.
+    variables: Map<Definition, Local>,
+
+    /// The variables declared in each enclosing block (innermost last),
+    /// in declaration order.
+    scopes: Vec<Vec<Local>>,
+
+    /// The enclosing loops, innermost last.
+    loops: Vec<LoopTarget>,
+
+    return_local: Local,
+
+    /// For each enclosing `atomic` block (innermost last), the number of
+    /// scopes outside it.
+    atomic_scopes: Vec<usize>,
+}
+
+#[derive(Clone)]
+struct LoopTarget {
+    break_block: BasicBlock,
+    continue_block: BasicBlock,
+
+    /// Where the value of `break` is stored.
+    destination: Place,
+
+    /// The number of scopes and `atomic` blocks outside the loop.
+    scope_depth: usize,
+    atomic_depth: usize,
+}
+
+impl Lower<'_> {
+    fn new_block(&mut self) -> BasicBlock {
+        let block = BasicBlock::from(self.blocks.len());
+        self.blocks.push(BasicBlockData {
+            statements: vec![],
+            terminator: Terminator::Error,
+        });
+        block
+    }
+
+    fn new_local(&mut self, name: Option<Word>, mode: StorageMode, origin: LocalOrigin) -> Local {
+        let local = Local::from(self.locals.len());
+        self.locals.push(LocalData { name, mode, origin });
+        local
+    }
+
+    fn temporary(&mut self, expr: Expr) -> Local {
+        self.new_local(None, StorageMode::Var, LocalOrigin::Temporary(expr))
+    }
+
+    fn push(&mut self, kind: StatementKind, origin: Origin) {
+        self.blocks[self.current]
+            .statements
+            .push(Statement { kind, origin });
+    }
+
+    fn assign(&mut self, destination: Place, rvalue: Rvalue, expr: Expr) {
+        self.push(
+            StatementKind::Assign(destination, rvalue),
+            Origin::Expr(expr),
+        );
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.blocks[self.current].terminator = terminator;
+    }
+
+    /// Ends the current block with `terminator`, which does not fall through.
+    /// Anything lowered afterwards goes into a fresh, unreachable block.
+    fn jump(&mut self, terminator: Terminator) {
+        self.terminate(terminator);
+        self.current = self.new_block();
+    }
+
+    /// Ends the current block by jumping to `target`, which becomes the
+    /// current block.
+    fn goto(&mut self, target: BasicBlock) {
+        self.terminate(Terminator::Goto(target));
+        self.current = target;
+    }
+
+    /// Pops the innermost scope, dropping its variables.
+    fn pop_scope(&mut self, origin: Origin) {
+        let scope = self.scopes.pop().unwrap();
+        for local in scope {
+            self.push(StatementKind::Drop(local), origin);
+        }
+    }
+
+    /// Drops the variables of the scopes and ends the `atomic` blocks
+    /// that a jump leaves, leaving `scope_depth` scopes and
+    /// `atomic_depth` atomic blocks. As when the blocks end normally,
+    /// the variables declared inside an `atomic` block are dropped
+    /// before it ends.
+    fn exit_scopes(&mut self, scope_depth: usize, atomic_depth: usize, expr: Expr) {
+        let mut atomic_scopes = self.atomic_scopes[atomic_depth..].to_vec();
+        for depth in (scope_depth..self.scopes.len()).rev() {
+            while atomic_scopes
+                .last()
+                .map_or(false, |&outside| outside > depth)
+            {
+                atomic_scopes.pop();
+                self.push(StatementKind::EndAtomic, Origin::Expr(expr));
+            }
+            for local in self.scopes[depth].clone() {
+                self.push(StatementKind::Drop(local), Origin::Expr(expr));
+            }
+        }
+        for _ in atomic_scopes {
+            self.push(StatementKind::EndAtomic, Origin::Expr(expr));
+        }
+    }
+
+    fn lower_block_contents(&mut self, block: Block, destination: Place) {
+        let exprs = &self.ast.tables[block].exprs;
+        match exprs.split_last() {
+            Some((&last, others)) => {
+                for &expr in others {
+                    self.lower_to_temporary(expr);
+                }
+                self.lower_into(last, destination);
+            }
+            None => self.push(
+                StatementKind::Assign(destination, Rvalue::Unit),
+                Origin::Block(block),
+            ),
+        }
+    }
+
+    fn lower_to_temporary(&mut self, expr: Expr) -> Local {
+        let temporary = self.temporary(expr);
+        self.lower_into(expr, Place::from(temporary));
+        temporary
+    }
+
+    fn lower_to_temporaries(&mut self, exprs: &[Expr]) -> Vec<Local> {
+        exprs
+            .iter()
+            .map(|&expr| self.lower_to_temporary(expr))
+            .collect()
+    }
+
+    /// Lowers `expr`, storing its value into `destination`.
+    fn lower_into(&mut self, expr: Expr, destination: Place) {
+        match &self.ast.tables[expr] {
+            ExprData::Id(_) | ExprData::Dot(..) | ExprData::Index(..) | ExprData::Give(_) => {
+                let place = self.lower_place(expr);
+                self.assign(destination, Rvalue::Give(place), expr);
+            }
+
+            ExprData::Share(place) => {
+                let place = self.lower_place(*place);
+                self.assign(destination, Rvalue::Share(place), expr);
+            }
+
+            ExprData::Lease(place) => {
+                let place = self.lower_place(*place);
+                self.assign(destination, Rvalue::Lease(place), expr);
+            }
+
+            ExprData::StringLiteral(word) => {
+                self.assign(destination, Rvalue::String(*word), expr);
+            }
+
+            ExprData::NumberLiteral(number) => {
+                self.assign(destination, Rvalue::Number(*number), expr);
+            }
+
+            ExprData::Concatenate(exprs) => {
+                // Interpolated places are read where they are, rather than given
+                // away into temporaries.
+                let places = exprs
+                    .iter()
+                    .map(|&piece| match &self.ast.tables[piece] {
+                        ExprData::Id(_) | ExprData::Dot(..) | ExprData::Index(..) => {
+                            self.lower_place(piece)
+                        }
+                        _ => Place::from(self.lower_to_temporary(piece)),
+                    })
+                    .collect();
+                self.assign(destination, Rvalue::Concatenate(places), expr);
+            }
+
+            ExprData::Tuple(exprs) if exprs.is_empty() => {
+                self.assign(destination, Rvalue::Unit, expr);
+            }
+
+            ExprData::Tuple(exprs) => {
+                let locals = self.lower_to_temporaries(exprs);
+                self.assign(destination, Rvalue::Tuple(locals), expr);
+            }
+
+            ExprData::List(exprs) => {
+                let locals = self.lower_to_temporaries(exprs);
+                self.assign(destination, Rvalue::List(locals), expr);
+            }
+
+            ExprData::Await(future) => {
+                let future = self.lower_to_temporary(*future);
+                self.assign(destination, Rvalue::Await(future), expr);
+            }
+
+            ExprData::Call(callee, named_exprs) => {
+                let callee = self.lower_to_temporary(*callee);
+                let arguments = named_exprs
+                    .iter()
+                    .map(|&named_expr| {
+                        let named_expr = &self.ast.tables[named_expr];
+                        (named_expr.name, self.lower_to_temporary(named_expr.expr))
+                    })
+                    .collect();
+                self.assign(destination, Rvalue::Call(callee, arguments), expr);
+            }
+
+            ExprData::Var(mode, name, initializer) => {
+                let local = self.new_local(Some(*name), *mode, LocalOrigin::Var(expr));
+                self.lower_into(*initializer, Place::from(local));
+
+                // Redeclaring a variable in the same block drops the old one.
+                let scope = self.scopes.last_mut().unwrap();
+                let locals = &self.locals;
+                if let Some(index) = scope
+                    .iter()
+                    .position(|&old| locals[old].name == Some(*name))
+                {
+                    let old = scope.remove(index);
+                    self.push(StatementKind::Drop(old), Origin::Expr(expr));
+                }
+                self.scopes.last_mut().unwrap().push(local);
+                self.variables.insert(Definition::Local(expr), local);
+                self.assign(destination, Rvalue::Unit, expr);
+            }
+
+            ExprData::Parenthesized(inner) => self.lower_into(*inner, destination),
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                let condition = self.lower_to_temporary(*condition);
+                let then_block = self.new_block();
+                let else_block = self.new_block();
+                let join_block = self.new_block();
+                self.terminate(Terminator::If(condition, then_block, else_block));
+
+                self.current = then_block;
+                self.lower_into(*then_expr, destination.clone());
+                self.goto(join_block);
+
+                self.current = else_block;
+                match else_expr {
+                    Some(else_expr) => self.lower_into(*else_expr, destination),
+                    None => self.assign(destination, Rvalue::Unit, expr),
+                }
+                self.goto(join_block);
+            }
+
+            ExprData::Loop(body) => {
+                let body_block = self.new_block();
+                let exit_block = self.new_block();
+                self.goto(body_block);
+                self.lower_loop_body(*body, body_block, exit_block, destination);
+                self.current = exit_block;
+            }
+
+            ExprData::While(condition, body) => {
+                let condition_block = self.new_block();
+                let body_block = self.new_block();
+                let exit_block = self.new_block();
+                self.goto(condition_block);
+                let condition = self.lower_to_temporary(*condition);
+                self.terminate(Terminator::If(condition, body_block, exit_block));
+
+                // A `while` loop always produces `()`; values given to
+                // `break` are discarded.
+                self.current = body_block;
+                let discarded = Place::from(self.temporary(expr));
+                self.lower_loop_body(*body, condition_block, exit_block, discarded);
+                self.current = exit_block;
+                self.assign(destination, Rvalue::Unit, expr);
+            }
+
+            ExprData::Break(value) => {
+                let target = match self.loops.last() {
+                    Some(target) => target.clone(),
+                    None => {
+                        if let Some(value) = *value {
+                            self.lower_to_temporary(value);
+                        }
+                        self.jump(Terminator::Error);
+                        return;
+                    }
+                };
+                match *value {
+                    Some(value) => self.lower_into(value, target.destination),
+                    None => self.assign(target.destination, Rvalue::Unit, expr),
+                }
+                self.exit_scopes(target.scope_depth, target.atomic_depth, expr);
+                self.jump(Terminator::Goto(target.break_block));
+            }
+
+            ExprData::Continue => match self.loops.last() {
+                Some(target) => {
+                    let target = target.clone();
+                    self.exit_scopes(target.scope_depth, target.atomic_depth, expr);
+                    self.jump(Terminator::Goto(target.continue_block));
+                }
+                None => self.jump(Terminator::Error),
+            },
+
+            ExprData::Return(value) => {
+                let return_local = Place::from(self.return_local);
+                match *value {
+                    Some(value) => self.lower_into(value, return_local),
+                    None => self.assign(return_local, Rvalue::Unit, expr),
+                }
+                self.exit_scopes(0, 0, expr);
+                self.jump(Terminator::Return);
+            }
+
+            ExprData::Block(block) => {
+                self.scopes.push(vec![]);
+                self.lower_block_contents(*block, destination);
+                self.pop_scope(Origin::Block(*block));
+            }
+
+            ExprData::Closure(mode, _, body) => {
+                let mut variables = vec![];
+                self.captured_variables(*body, &mut variables);
+                let mut captures = vec![];
+                for (name, id_expr) in variables {
+                    let temporary = self.temporary(id_expr);
+                    let place = self.lower_place(id_expr);
+                    let rvalue = match mode {
+                        CaptureMode::Give => Rvalue::Give(place),
+                        CaptureMode::Lease => Rvalue::Lease(place),
+                        CaptureMode::Share => Rvalue::Share(place),
+                    };
+                    self.assign(Place::from(temporary), rvalue, id_expr);
+                    captures.push((name, temporary));
+                }
+                self.assign(destination, Rvalue::Closure(*mode, expr, captures), expr);
+            }
+
+            ExprData::Atomic(body) => {
+                self.push(StatementKind::StartAtomic, Origin::Expr(expr));
+                self.atomic_scopes.push(self.scopes.len());
+                self.lower_into(*body, destination);
+                self.atomic_scopes.pop();
+                self.push(StatementKind::EndAtomic, Origin::Expr(expr));
+            }
+
+            ExprData::Unary(op, operand) => {
+                let operand = self.lower_to_temporary(*operand);
+                self.assign(destination, Rvalue::Unary(*op, operand), expr);
+            }
+
+            ExprData::Op(lhs, op @ (Op::AndAnd | Op::OrOr), rhs) => {
+                // Only evaluate `rhs` if `lhs` does not decide the result.
+                let lhs = self.lower_to_temporary(*lhs);
+                let rhs_block = self.new_block();
+                let decided_block = self.new_block();
+                let join_block = self.new_block();
+                self.terminate(match op {
+                    Op::AndAnd => Terminator::If(lhs, rhs_block, decided_block),
+                    _ => Terminator::If(lhs, decided_block, rhs_block),
+                });
+
+                self.current = rhs_block;
+                self.lower_into(*rhs, destination.clone());
+                self.goto(join_block);
+
+                self.current = decided_block;
+                self.assign(destination, Rvalue::Give(Place::from(lhs)), expr);
+                self.goto(join_block);
+            }
+
+            ExprData::Op(lhs, op, rhs) => {
+                let lhs = self.lower_to_temporary(*lhs);
+                let rhs = self.lower_to_temporary(*rhs);
+                self.assign(destination, Rvalue::Op(lhs, *op, rhs), expr);
+            }
+
+            ExprData::OpEq(place_expr, op, rhs) => {
+                // The place, including any index in it, is lowered only once
+                // and used both to read the old value and to store the new one.
+                let place = self.lower_place(*place_expr);
+                let lhs = self.temporary(*place_expr);
+                self.assign(Place::from(lhs), Rvalue::Give(place.clone()), expr);
+                let rhs = self.lower_to_temporary(*rhs);
+                let value = self.temporary(expr);
+                self.assign(Place::from(value), Rvalue::Op(lhs, *op, rhs), expr);
+                self.assign(place, Rvalue::Give(Place::from(value)), expr);
+                self.assign(destination, Rvalue::Unit, expr);
+            }
+
+            ExprData::Assign(place_expr, rhs) => {
+                let value = self.lower_to_temporary(*rhs);
+                let place = self.lower_place(*place_expr);
+                self.assign(place, Rvalue::Give(Place::from(value)), expr);
+                self.assign(destination, Rvalue::Unit, expr);
+            }
+
+            ExprData::Error => self.assign(destination, Rvalue::Error, expr),
+        }
+    }
+
+    /// Lowers the body of a loop that starts at `body_block` and continues
+    /// at `continue_block`.
+    fn lower_loop_body(
+        &mut self,
+        body: Expr,
+        continue_block: BasicBlock,
+        break_block: BasicBlock,
+        destination: Place,
+    ) {
+        self.loops.push(LoopTarget {
+            break_block,
+            continue_block,
+            destination,
+            scope_depth: self.scopes.len(),
+            atomic_depth: self.atomic_scopes.len(),
+        });
+        self.lower_to_temporary(body);
+        self.loops.pop();
+        self.terminate(Terminator::Goto(continue_block));
+    }
+
+    /// Lowers `expr` as a place. Expressions that do not name a variable,
+    /// field or element are stored into a temporary.
+    fn lower_place(&mut self, expr: Expr) -> Place {
+        match &self.ast.tables[expr] {
+            ExprData::Id(_) => {
+                let definition = self.resolutions.definition(expr);
+                if let Some(&local) = definition.and_then(|d| self.variables.get(&d)) {
+                    return Place::from(local);
+                }
+                let rvalue = match definition {
+                    Some(
+                        definition @ (Definition::Class(_)
+                        | Definition::Function(_)
+                        | Definition::Intrinsic(_)),
+                    ) => Rvalue::Item(definition),
+                    _ => Rvalue::Error,
+                };
+                let temporary = self.temporary(expr);
+                self.assign(Place::from(temporary), rvalue, expr);
+                Place::from(temporary)
+            }
+
+            ExprData::Dot(base, field) => {
+                let mut place = self.lower_place(*base);
+                place.projections.push(Projection::Field(*field));
+                place
+            }
+
+            ExprData::Index(base, index) => {
+                // The index is computed first, as in the interpreter.
+                let index = self.lower_to_temporary(*index);
+                let mut place = self.lower_place(*base);
+                place.projections.push(Projection::Index(index));
+                place
+            }
+
+            ExprData::Give(inner) | ExprData::Parenthesized(inner) => self.lower_place(*inner),
+
+            _ => Place::from(self.lower_to_temporary(expr)),
+        }
+    }
+
+    /// Collects the variables lowered so far that `expr`, part of a closure
+    /// body, refers to, along with the first expression naming each one.
+    fn captured_variables(&self, expr: Expr, variables: &mut Vec<(Word, Expr)>) {
+        match &self.ast.tables[expr] {
+            ExprData::Id(name) => {
+                let captured = self
+                    .resolutions
+                    .definition(expr)
+                    .map_or(false, |d| self.variables.contains_key(&d));
+                if captured && !variables.iter().any(|(n, _)| n == name) {
+                    variables.push((*name, expr));
+                }
+            }
+
+            ExprData::StringLiteral(_)
+            | ExprData::NumberLiteral(_)
+            | ExprData::Continue
+            | ExprData::Error => {}
+
+            ExprData::Dot(expr, _)
+            | ExprData::Await(expr)
+            | ExprData::Share(expr)
+            | ExprData::Lease(expr)
+            | ExprData::Give(expr)
+            | ExprData::Var(_, _, expr)
+            | ExprData::Parenthesized(expr)
+            | ExprData::Loop(expr)
+            | ExprData::Closure(_, _, expr)
+            | ExprData::Atomic(expr)
+            | ExprData::Unary(_, expr) => self.captured_variables(*expr, variables),
+
+            ExprData::Break(expr) | ExprData::Return(expr) => {
+                if let Some(expr) = *expr {
+                    self.captured_variables(expr, variables);
+                }
+            }
+
+            ExprData::Concatenate(exprs) | ExprData::Tuple(exprs) | ExprData::List(exprs) => {
+                for &expr in exprs {
+                    self.captured_variables(expr, variables);
+                }
+            }
+
+            ExprData::Block(block) => {
+                for &expr in &self.ast.tables[*block].exprs {
+                    self.captured_variables(expr, variables);
+                }
+            }
+
+            ExprData::Call(callee, arguments) => {
+                self.captured_variables(*callee, variables);
+                for &argument in arguments {
+                    self.captured_variables(self.ast.tables[argument].expr, variables);
+                }
+            }
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                self.captured_variables(*condition, variables);
+                self.captured_variables(*then_expr, variables);
+                if let Some(else_expr) = *else_expr {
+                    self.captured_variables(else_expr, variables);
+                }
+            }
+
+            ExprData::While(lhs, rhs)
+            | ExprData::Index(lhs, rhs)
+            | ExprData::Op(lhs, _, rhs)
+            | ExprData::OpEq(lhs, _, rhs)
+            | ExprData::Assign(lhs, rhs) => {
+                self.captured_variables(*lhs, variables);
+                self.captured_variables(*rhs, variables);
+            }
+        }
+    }
+}
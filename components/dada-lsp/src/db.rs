@@ -2,8 +2,9 @@ use crossbeam_channel::Sender;
 use dada_ir::{span::Offset, word::Word};
 use lsp_server::Message;
 use lsp_types::{
-    notification::PublishDiagnostics, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
-    DidOpenTextDocumentParams, Position, PublishDiagnosticsParams, Range, Url,
+    notification::PublishDiagnostics, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidOpenTextDocumentParams, Location, Position,
+    PublishDiagnosticsParams, Range, Url,
 };
 use salsa::ParallelDatabase;
 
@@ -100,7 +101,25 @@ impl DadaLspMethods for dada_db::Db {
         let code = None;
         let source = None;
         let message = dada_diagnostic.message;
-        let related_information = None;
+        let related_information = if dada_diagnostic.labels.is_empty() {
+            None
+        } else {
+            Url::parse(dada_diagnostic.filename.as_str(self))
+                .ok()
+                .map(|uri| {
+                    dada_diagnostic
+                        .labels
+                        .into_iter()
+                        .map(|label| DiagnosticRelatedInformation {
+                            location: Location {
+                                uri: uri.clone(),
+                                range: self.lsp_range(dada_diagnostic.filename, label.span),
+                            },
+                            message: label.message,
+                        })
+                        .collect()
+                })
+        };
         let tags = None;
         Diagnostic {
             range,
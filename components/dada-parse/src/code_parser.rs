@@ -1,9 +1,31 @@
 use crate::parser::Parser;
 
-use dada_ir::code::{Ast, Code};
+use dada_ir::{
+    code::{Ast, Code, Spans},
+    func::Function,
+};
 
+/// Parses the body of `code`, returning its [`Ast`] along with the
+/// [`Spans`] that map each expression back to the source. Other crates
+/// use the [`ast`] and [`spans`] queries instead.
 #[salsa::memoized(in crate::Jar ref)]
-pub fn parse_code(db: &dyn crate::Db, code: Code) -> Ast {
+pub(crate) fn parse_code(db: &dyn crate::Db, code: Code) -> (Ast, Spans) {
     let token_tree = code.tokens(db);
     Parser::new(db, token_tree).parse_ast()
 }
+
+/// The [`Ast`] of the body of `function`. It is kept apart from its
+/// [`spans`] so that edits that only move code around, such as changes to
+/// whitespace, produce an equal `Ast` and leave the queries that depend
+/// only on it untouched.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn ast(db: &dyn crate::Db, function: Function) -> Ast {
+    parse_code(db, function.code(db)).0.clone()
+}
+
+/// The [`Spans`] of the expressions in the body of `function`, for
+/// reporting locations in the source.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn spans(db: &dyn crate::Db, function: Function) -> Spans {
+    parse_code(db, function.code(db)).1.clone()
+}
@@ -4,16 +4,25 @@
 
 mod code_parser;
 mod file_parser;
+mod parameter_parser;
 mod parser;
 mod token_test;
 mod tokens;
 
 #[salsa::jar(Db)]
-pub struct Jar(file_parser::parse_file, code_parser::parse_code);
+pub struct Jar(
+    file_parser::parse_file,
+    code_parser::parse_code,
+    code_parser::ast,
+    code_parser::spans,
+    parameter_parser::parse_fields,
+    parameter_parser::parse_parameters,
+);
 
 pub trait Db: salsa::DbWithJar<Jar> + dada_lex::Db + dada_ir::Db {}
 impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_lex::Db + dada_ir::Db {}
 
-pub use code_parser::parse_code;
+pub use code_parser::{ast, spans};
 pub use file_parser::parse_file;
+pub use parameter_parser::{parse_fields, parse_parameters};
 pub mod prelude;
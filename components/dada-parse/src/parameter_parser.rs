@@ -0,0 +1,20 @@
+use crate::parser::Parser;
+
+use dada_ir::{
+    class::{Class, Field},
+    func::{Function, Parameter},
+};
+
+/// Parses the fields declared by `class`, e.g. `(var x, var y, atomic z)`.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn parse_fields(db: &dyn crate::Db, class: Class) -> Vec<Field> {
+    let token_tree = class.field_tokens(db);
+    Parser::new(db, token_tree).parse_only_fields()
+}
+
+/// Parses the parameters declared by `function`, e.g. `(var x, y: String)`.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn parse_parameters(db: &dyn crate::Db, function: Function) -> Vec<Parameter> {
+    let token_tree = function.argument_tokens(db);
+    Parser::new(db, token_tree).parse_only_parameters()
+}
@@ -6,6 +6,7 @@ use dada_ir::{
 
 mod code;
 mod items;
+mod parameters;
 pub(crate) struct Parser<'me> {
     db: &'me dyn crate::Db,
     filename: Word,
@@ -102,7 +103,7 @@ impl<'me> Parser<'me> {
     /// If the next token is an opening delimiter, like `(` or `{`,
     /// then consumes it, the token-tree that follows, and the closing delimiter (if present).
     /// Returns the token tree + the span including delimiters.
-    /// Reports an error if there is no closing delimiter.
+    /// (If there is no closing delimiter, the lexer has already reported it.)
     fn delimited(&mut self, delimiter: char) -> Option<(Span, TokenTree)> {
         let (open_span, _) = self.eat(Token::Delimiter(delimiter))?;
 
@@ -111,8 +112,7 @@ impl<'me> Parser<'me> {
 
         // Consume closing delimiter (if present)
         let closing_delimiter = dada_lex::closing_delimiter(delimiter);
-        self.eat(Token::Delimiter(closing_delimiter))
-            .or_report_error(self, || format!("expected `{closing_delimiter}`"));
+        self.eat(Token::Delimiter(closing_delimiter));
 
         let span = open_span.to(self.tokens.last_span());
         Some((span, token_tree))
@@ -146,6 +146,7 @@ impl<'me> Parser<'me> {
                 filename: self.filename(),
                 span,
                 message: message.as_ref().to_string(),
+                labels: vec![],
             },
         );
     }
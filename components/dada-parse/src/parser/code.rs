@@ -1,24 +1,30 @@
 use crate::{
     parser::Parser,
-    token_test::{Identifier, StringLiteral},
+    token_test::{FormatStringLiteral, Identifier, NumberLiteral, Prefix, StringLiteral},
 };
 
 use dada_id::InternValue;
 use dada_ir::{
     code::{
-        Ast, Block, BlockData, Expr, ExprData, NamedExpr, NamedExprSpan, PushSpan, Spans, Tables,
+        Ast, Block, BlockData, CaptureMode, Expr, ExprData, NamedExpr, NamedExprSpan, PushSpan,
+        Spans, Tables,
     },
+    format_string::{FormatString, FormatStringSectionData},
     kw::Keyword,
-    op::Op,
+    number::{Number, NumberError},
+    op::{binary_ops, Op},
+    span::Span,
+    storage_mode::StorageMode,
     token::Token,
     token_tree::TokenTree,
+    word::Word,
 };
 use salsa::AsId;
 
 use super::OrReportError;
 
 impl Parser<'_> {
-    pub(crate) fn parse_ast(&mut self) -> Ast {
+    pub(crate) fn parse_ast(&mut self) -> (Ast, Spans) {
         let mut tables = Tables::default();
         let mut spans = Spans::default();
 
@@ -29,7 +35,7 @@ impl Parser<'_> {
         };
 
         let block = code_parser.parse_only_block_contents();
-        Ast { tables, block }
+        (Ast { tables, block }, spans)
     }
 }
 
@@ -68,6 +74,30 @@ impl CodeParser<'_, '_> {
         exprs
     }
 
+    /// Parses the contents of `(...)`: a single expression, or the elements of a tuple
+    /// (which has a comma after each element, except perhaps the last one).
+    /// Returns the expressions and whether there was a trailing comma.
+    pub(crate) fn parse_only_parenthesized(&mut self) -> (Vec<Expr>, bool) {
+        let mut exprs = vec![];
+        let mut trailing_comma = false;
+        while let Some(expr) = self.parse_expr() {
+            exprs.push(expr);
+            trailing_comma = self.eat_op(Op::Comma).is_some();
+            if !trailing_comma {
+                break;
+            }
+        }
+        self.report_error_if_more_tokens("extra tokens after expression");
+        (exprs, trailing_comma)
+    }
+
+    /// Parses the elements of a list literal; expects to consume all available tokens (and errors if there are extra).
+    pub(crate) fn parse_only_list_elements(&mut self) -> Vec<Expr> {
+        let exprs = self.parse_list(Some(Op::Comma), CodeParser::parse_expr);
+        self.report_error_if_more_tokens("extra tokens after end of list");
+        exprs
+    }
+
     /// Parses a single expression (and errors if there are extra tokens)
     pub(crate) fn parse_only_expr(&mut self) -> Expr {
         if let Some(expr) = self.parse_expr() {
@@ -84,11 +114,10 @@ impl CodeParser<'_, '_> {
 
     fn add<D, K>(&mut self, data: D, span: K::Span) -> K
     where
-        D: std::hash::Hash + Eq + std::fmt::Debug,
+        D: std::hash::Hash + Eq,
         Tables: InternValue<D, Key = K>,
         K: PushSpan + AsId,
     {
-        eprintln!("add {data:?}");
         let key = self.tables.add(data);
         key.push_span(&mut self.spans, span);
         key
@@ -134,76 +163,280 @@ impl CodeParser<'_, '_> {
 
     /// ```
     /// Expr := Id
+    ///       | StringLiteral
+    ///       | FormatString
+    ///       | NumberLiteral
     ///       | UnaryOp Expr
     ///       | `if` Expr Block [`else` Block]
     ///       | `while` Expr Block
     ///       | `loop` Block
+    ///       | `atomic` Block
     ///       | `continue`
     ///       | `break` [Expr]
     ///       | `return` [Expr]
     ///       | Block
+    ///       | Closure
+    ///       | `(` Expr `)`
+    ///       | `(` [Expr `,` ...] `)`
+    ///       | `[` [Expr `,` ...] `]`
     ///       | Expr . Ident
+    ///       | Expr `[` Expr `]`
     ///       | Expr BinaryOp Expr
     ///       | Expr ( args )
+    ///       | [StorageMode] Id `=` Expr
+    ///       | Expr `:=` Expr
+    ///       | Expr BinaryOp`=` Expr
     /// ```
     pub(crate) fn parse_expr(&mut self) -> Option<Expr> {
-        self.parse_expr_3()
-    }
+        if let Some(expr) = self.parse_local_variable_decl() {
+            return Some(expr);
+        }
 
-    pub(crate) fn parse_expr_3(&mut self) -> Option<Expr> {
-        let expr = self.parse_expr_2()?;
+        let expr = self.parse_binary_expr(0)?;
+
+        if self.eat_op(Op::Equal).is_some() {
+            // `x = ...` declares a (shared) variable; anything else on the left is an error.
+            let rhs = self.parse_required_expr(Op::Equal);
+            let span = self.spans[expr].to(self.spans[rhs]);
+            return Some(match self.tables[expr] {
+                ExprData::Id(name) => self.add(ExprData::Var(StorageMode::Shared, name, rhs), span),
+                ExprData::Dot(..) => {
+                    self.report_error(
+                        span,
+                        "`=` declares a new variable; use `:=` to assign to a field",
+                    );
+                    self.add(ExprData::Assign(expr, rhs), span)
+                }
+                ExprData::Index(..) => {
+                    self.report_error(
+                        span,
+                        "`=` declares a new variable; use `:=` to assign to an element",
+                    );
+                    self.add(ExprData::Assign(expr, rhs), span)
+                }
+                _ => {
+                    self.report_error(self.spans[expr], "expected a variable name before `=`");
+                    self.add(ExprData::Assign(expr, rhs), span)
+                }
+            });
+        }
 
-        if let Some(expr1) = self.parse_binop(expr, &[Op::Plus, Op::Minus]) {
-            return Some(expr1);
+        if self.eat_op(Op::ColonEqual).is_some() {
+            self.check_assignment_target(expr, Op::ColonEqual);
+            let rhs = self.parse_required_expr(Op::ColonEqual);
+            let span = self.spans[expr].to(self.spans[rhs]);
+            return Some(self.add(ExprData::Assign(expr, rhs), span));
+        }
+
+        for binary_op in binary_ops(self.db) {
+            if self.eat_op(binary_op.assign_op).is_some() {
+                self.check_assignment_target(expr, binary_op.assign_op);
+                let rhs = self.parse_required_expr(binary_op.assign_op);
+                let span = self.spans[expr].to(self.spans[rhs]);
+                return Some(self.add(ExprData::OpEq(expr, binary_op.binary_op, rhs), span));
+            }
         }
 
         Some(expr)
     }
 
-    pub(crate) fn parse_expr_2(&mut self) -> Option<Expr> {
-        let expr = self.parse_expr_1()?;
+    /// ```
+    /// `var` Id `=` Expr
+    /// `shared` Id `=` Expr
+    /// `atomic` Id `=` Expr
+    /// ```
+    ///
+    /// (`atomic` followed by a block is an atomic block instead.)
+    fn parse_local_variable_decl(&mut self) -> Option<Expr> {
+        let tokens = self.tokens;
+        let (mode_span, mode) = [
+            (Keyword::Var, StorageMode::Var),
+            (Keyword::Shared, StorageMode::Shared),
+            (Keyword::Atomic, StorageMode::Atomic),
+        ]
+        .into_iter()
+        .find_map(|(kw, mode)| self.eat(kw).map(|(span, _)| (span, mode)))?;
+
+        if mode == StorageMode::Atomic && self.peek(Token::Delimiter('{')).is_some() {
+            self.tokens = tokens;
+            return None;
+        }
+
+        let Some((_, name)) = self
+            .eat(Identifier)
+            .or_report_error(self, || format!("expected variable name"))
+        else {
+            return Some(self.add(ExprData::Error, self.span_consumed_since(mode_span)));
+        };
+
+        self.eat_op(Op::Equal)
+            .or_report_error(self, || format!("expected `=` after variable name"));
+        let initializer = self.parse_required_expr(Op::Equal);
+        let span = self.span_consumed_since(mode_span);
+        Some(self.add(ExprData::Var(mode, name, initializer), span))
+    }
 
-        if let Some(expr1) = self.parse_binop(expr, &[Op::DividedBy, Op::Times]) {
-            return Some(expr1);
+    /// Reports an error unless `expr` is something that can be assigned to with `op`.
+    fn check_assignment_target(&mut self, expr: Expr, op: Op) {
+        match self.tables[expr] {
+            ExprData::Id(_) | ExprData::Dot(..) | ExprData::Index(..) => {}
+            _ => self.report_error(
+                self.spans[expr],
+                format!("invalid target for `{op}`; expected a variable, field or element"),
+            ),
         }
+    }
 
-        Some(expr)
+    /// Parses a chain of binary operators whose precedence is at least `min_precedence`,
+    /// using the levels in [`BINARY_OPERATORS`].
+    pub(crate) fn parse_binary_expr(&mut self, min_precedence: usize) -> Option<Expr> {
+        let mut lhs = self.parse_unary_expr()?;
+
+        // Precedence of the operator that produced `lhs`, if any.
+        let mut lhs_precedence = None;
+
+        while let Some((op, precedence, associativity)) = self.eat_binary_op(min_precedence) {
+            if associativity == Associativity::None && lhs_precedence == Some(precedence) {
+                self.report_error(
+                    self.span_consumed_since(self.spans[lhs]),
+                    format!("comparison operators cannot be chained; use `&&` before `{op}`"),
+                );
+            }
+
+            let rhs = self
+                .parse_binary_expr(precedence + 1)
+                .or_report_error(self, || format!("expected expression after `{op}`"))
+                .or_dummy_expr(self);
+            let span = self.spans[lhs].to(self.spans[rhs]);
+            lhs = self.add(ExprData::Op(lhs, op, rhs), span);
+            lhs_precedence = Some(precedence);
+        }
+
+        Some(lhs)
+    }
+
+    /// Consumes the next token if it is a binary operator with at least `min_precedence`,
+    /// returning the operator along with its precedence and associativity.
+    fn eat_binary_op(&mut self, min_precedence: usize) -> Option<(Op, usize, Associativity)> {
+        BINARY_OPERATORS
+            .iter()
+            .enumerate()
+            .skip(min_precedence)
+            .find_map(|(precedence, &(associativity, ops))| {
+                let op = ops.iter().copied().find(|&op| self.eat_op(op).is_some())?;
+                Some((op, precedence, associativity))
+            })
+    }
+
+    /// ```
+    /// `-` Expr
+    /// `!` Expr
+    /// ```
+    ///
+    /// Unary operators bind more tightly than binary ones, but less tightly than
+    /// `.` and calls: `-a.b` is `-(a.b)`. The exception is a `-` directly before a
+    /// number literal, which is part of the literal, so that `-9223372036854775808`
+    /// is a valid integer.
+    pub(crate) fn parse_unary_expr(&mut self) -> Option<Expr> {
+        for op in [Op::Minus, Op::Not] {
+            if let Some(op_span) = self.eat_op(op) {
+                if op == Op::Minus {
+                    if let Some((span, text)) = self.eat(NumberLiteral) {
+                        let literal = self.number_literal(op_span.to(span), text, true);
+                        return Some(self.parse_postfix(literal));
+                    }
+                }
+
+                let operand = self
+                    .parse_unary_expr()
+                    .or_report_error(self, || format!("expected expression after `{op}`"))
+                    .or_dummy_expr(self);
+                let span = op_span.to(self.spans[operand]);
+                return Some(self.add(ExprData::Unary(op, operand), span));
+            }
+        }
+
+        self.parse_expr_1()
     }
 
     pub(crate) fn parse_expr_1(&mut self) -> Option<Expr> {
         let expr = self.parse_expr_0()?;
+        Some(self.parse_postfix(expr))
+    }
 
-        if let Some(_) = self.eat_op(Op::Dot) {
-            if let Some((id_span, id)) = self.eat(Identifier) {
-                let span = self.spans[expr].to(id_span);
-                return Some(self.add(ExprData::Dot(expr, id), span));
-            } else if let Some((kw_span, _)) = self.eat(Keyword::Async) {
-                let span = self.spans[expr].to(kw_span);
-                return Some(self.add(ExprData::Await(expr), span));
-            } else if let Some((kw_span, _)) = self.eat(Keyword::Share) {
-                let span = self.spans[expr].to(kw_span);
-                return Some(self.add(ExprData::Share(expr), span));
-            } else if let Some((kw_span, _)) = self.eat(Keyword::Give) {
-                let span = self.spans[expr].to(kw_span);
-                return Some(self.add(ExprData::Give(expr), span));
-            } else if let Some((kw_span, _)) = self.eat(Keyword::Lease) {
-                let span = self.spans[expr].to(kw_span);
-                return Some(self.add(ExprData::Lease(expr), span));
-            } else {
-                self.parser
-                    .report_error_at_current_token("expected identifier after `.`");
+    /// Parses the `.field`, `.await`, calls, and indexing that follow `expr`.
+    fn parse_postfix(&mut self, mut expr: Expr) -> Expr {
+        loop {
+            if let Some(_) = self.eat_op(Op::Dot) {
+                if let Some((id_span, id)) = self.eat(Identifier) {
+                    let span = self.spans[expr].to(id_span);
+                    expr = self.add(ExprData::Dot(expr, id), span);
+                } else if let Some((kw_span, _)) = self.eat(Keyword::Await) {
+                    let span = self.spans[expr].to(kw_span);
+                    expr = self.add(ExprData::Await(expr), span);
+                } else if let Some((kw_span, _)) = self.eat(Keyword::Share) {
+                    let span = self.spans[expr].to(kw_span);
+                    expr = self.add(ExprData::Share(expr), span);
+                } else if let Some((kw_span, _)) = self.eat(Keyword::Give) {
+                    let span = self.spans[expr].to(kw_span);
+                    expr = self.add(ExprData::Give(expr), span);
+                } else if let Some((kw_span, _)) = self.eat(Keyword::Lease) {
+                    let span = self.spans[expr].to(kw_span);
+                    expr = self.add(ExprData::Lease(expr), span);
+                } else {
+                    self.parser
+                        .report_error_at_current_token("expected identifier after `.`");
+                    break;
+                }
+                continue;
+            }
+
+            if let Some((arg_span, token_tree)) = self.delimited('(') {
+                // `base(...)`
+                let named_exprs = self
+                    .with_sub_parser(token_tree, |sub_parser| sub_parser.parse_only_named_exprs());
+                let span = self.spans[expr].to(arg_span);
+                expr = self.add(ExprData::Call(expr, named_exprs), span);
+                continue;
             }
-        }
 
-        if let Some((arg_span, token_tree)) = self.delimited('(') {
-            // `base(...)`
-            let named_exprs =
-                self.with_sub_parser(token_tree, |sub_parser| sub_parser.parse_only_named_exprs());
-            let span = self.spans[expr].to(arg_span);
-            return Some(self.add(ExprData::Call(expr, named_exprs), span));
+            // A `[` on the next line starts a list literal instead.
+            if !self.tokens.skipped_newline() {
+                if let Some((index_span, token_tree)) = self.delimited('[') {
+                    // `base[index]`
+                    let index =
+                        self.with_sub_parser(token_tree, |sub_parser| sub_parser.parse_only_expr());
+                    let span = self.spans[expr].to(index_span);
+                    expr = self.add(ExprData::Index(expr, index), span);
+                    continue;
+                }
+            }
+
+            break;
         }
 
-        Some(expr)
+        expr
+    }
+
+    /// Adds the number literal `text`, negated if it was preceded by `-`. Malformed
+    /// literals were already reported by the lexer, but literals that are too large
+    /// are reported here, since that depends on the sign.
+    fn number_literal(&mut self, span: Span, text: Word, negated: bool) -> Expr {
+        let text = text.as_str(self.db);
+        let number = if negated {
+            Number::parse_negated(text)
+        } else {
+            Number::parse(text)
+        };
+        match number {
+            Ok(number) => self.add(ExprData::NumberLiteral(number), span),
+            Err(error) => {
+                if error == NumberError::TooLarge {
+                    self.parser.report_error(span, error.to_string());
+                }
+                self.add(ExprData::Error, span)
+            }
+        }
     }
 
     pub(crate) fn parse_expr_0(&mut self) -> Option<Expr> {
@@ -211,9 +444,17 @@ impl CodeParser<'_, '_> {
             Some(self.add(ExprData::Id(id), id_span))
         } else if let Some((span, text)) = self.eat(StringLiteral) {
             Some(self.add(ExprData::StringLiteral(text), span))
+        } else if let Some((span, format_string)) = self.eat(FormatStringLiteral) {
+            Some(self.parse_format_string(span, format_string))
+        } else if let Some(expr) = self.parse_byte_string() {
+            Some(expr)
+        } else if let Some((span, text)) = self.eat(NumberLiteral) {
+            Some(self.number_literal(span, text, false))
         } else if let Some(expr) = self.parse_block_expr() {
             // { ... }
             Some(expr)
+        } else if let Some(expr) = self.parse_closure() {
+            Some(expr)
         } else if let Some((if_span, _)) = self.eat(Keyword::If) {
             if let Some(condition) = self.parse_condition() {
                 let then_expr = self.parse_required_block_expr(Keyword::If);
@@ -235,14 +476,161 @@ impl CodeParser<'_, '_> {
                 self.report_error_at_current_token("expected `while` condition");
                 None
             }
+        } else if let Some((loop_span, _)) = self.eat(Keyword::Loop) {
+            let body = self.parse_required_block_expr(Keyword::Loop);
+            let span = self.span_consumed_since(loop_span);
+            Some(self.add(ExprData::Loop(body), span))
+        } else if let Some((span, _)) = self.eat(Keyword::Continue) {
+            Some(self.add(ExprData::Continue, span))
+        } else if let Some((break_span, _)) = self.eat(Keyword::Break) {
+            let value = self.parse_jump_value();
+            let span = self.span_consumed_since(break_span);
+            Some(self.add(ExprData::Break(value), span))
+        } else if let Some((return_span, _)) = self.eat(Keyword::Return) {
+            let value = self.parse_jump_value();
+            let span = self.span_consumed_since(return_span);
+            Some(self.add(ExprData::Return(value), span))
+        } else if let Some((atomic_span, _)) = self.eat(Keyword::Atomic) {
+            let body = self.parse_required_block_expr(Keyword::Atomic);
+            let span = self.span_consumed_since(atomic_span);
+            Some(self.add(ExprData::Atomic(body), span))
         } else if let Some((span, token_tree)) = self.delimited('(') {
-            let expr = self.with_sub_parser(token_tree, |subparser| subparser.parse_only_expr());
-            Some(self.add(ExprData::Parenthesized(expr), span))
+            let (exprs, trailing_comma) =
+                self.with_sub_parser(token_tree, |subparser| subparser.parse_only_parenthesized());
+            match &exprs[..] {
+                &[expr] if !trailing_comma => Some(self.add(ExprData::Parenthesized(expr), span)),
+                _ => Some(self.add(ExprData::Tuple(exprs), span)),
+            }
+        } else if let Some((span, token_tree)) = self.delimited('[') {
+            let exprs =
+                self.with_sub_parser(token_tree, |subparser| subparser.parse_only_list_elements());
+            Some(self.add(ExprData::List(exprs), span))
         } else {
             None
         }
     }
 
+    /// Lowers a string with embedded expressions, spanning `span`, into a `Concatenate`
+    /// of its text and the expressions parsed from each `{...}`.
+    fn parse_format_string(&mut self, span: Span, format_string: FormatString) -> Expr {
+        let sections = format_string.data(self.db).sections.clone();
+
+        // Strings with escapes or a prefix, like `r"..."`, are also lexed as format strings.
+        if let [section] = &sections[..] {
+            if let FormatStringSectionData::Text(word) = *section.data(self.db) {
+                return self.add(ExprData::StringLiteral(word), span);
+            }
+        }
+
+        // Each token tree knows its own span; the text in between runs from
+        // just after the opening quote or `}` to just before the next `{` or closing quote.
+        let mut text_start = span.start + 1u32;
+        let mut exprs = Vec::with_capacity(sections.len());
+        for (index, section) in sections.iter().enumerate() {
+            match *section.data(self.db) {
+                FormatStringSectionData::Text(word) => {
+                    let text_end = match sections.get(index + 1).map(|s| s.data(self.db)) {
+                        Some(FormatStringSectionData::TokenTree(tree)) => {
+                            tree.span(self.db).start - 1u32
+                        }
+                        _ => span.end - 1u32,
+                    };
+                    let text_span = Span::from(text_start, text_end.max(text_start));
+                    exprs.push(self.add(ExprData::StringLiteral(word), text_span));
+                }
+                FormatStringSectionData::TokenTree(tree) => {
+                    let expr =
+                        self.with_sub_parser(tree, |sub_parser| sub_parser.parse_only_expr());
+                    exprs.push(expr);
+                    text_start = tree.span(self.db).end + 1u32;
+                }
+            }
+        }
+
+        self.add(ExprData::Concatenate(exprs), span)
+    }
+
+    /// ```
+    /// `b"..."`
+    /// ```
+    ///
+    /// The lexer produces a byte string as a `b` prefix followed by a string that
+    /// contains only ASCII text. It evaluates to a list of the values of its bytes.
+    fn parse_byte_string(&mut self) -> Option<Expr> {
+        let tokens = self.tokens;
+        let (prefix_span, _) = self.eat(Prefix)?;
+        let (string_span, text) = if let Some((span, word)) = self.eat(StringLiteral) {
+            (span, word.as_str(self.db).to_string())
+        } else if let Some((span, format_string)) = self.eat(FormatStringLiteral) {
+            let text: String = format_string
+                .data(self.db)
+                .sections
+                .iter()
+                .filter_map(|section| match section.data(self.db) {
+                    FormatStringSectionData::Text(word) => Some(word.as_str(self.db)),
+                    FormatStringSectionData::TokenTree(_) => None,
+                })
+                .collect();
+            (span, text)
+        } else {
+            self.tokens = tokens;
+            return None;
+        };
+
+        let span = prefix_span.to(string_span);
+        let bytes = text
+            .bytes()
+            .map(|byte| self.add(ExprData::NumberLiteral(Number::Integer(byte.into())), span))
+            .collect();
+        Some(self.add(ExprData::List(bytes), span))
+    }
+
+    /// Parses the value after `break` or `return`, if any; it must start on the same line.
+    fn parse_jump_value(&mut self) -> Option<Expr> {
+        if self.tokens.skipped_newline() {
+            None
+        } else {
+            self.parse_expr()
+        }
+    }
+
+    /// ```
+    /// Closure := [CaptureMode] `fn` `(` Parameters `)` Block
+    /// CaptureMode := `give` | `lease` | `share`
+    /// ```
+    ///
+    /// Closures without a capture mode share the variables they capture.
+    fn parse_closure(&mut self) -> Option<Expr> {
+        let tokens = self.tokens;
+        let start_span = self.tokens.peek_span();
+        let mode = [
+            (Keyword::Give, CaptureMode::Give),
+            (Keyword::Lease, CaptureMode::Lease),
+            (Keyword::Share, CaptureMode::Share),
+        ]
+        .into_iter()
+        .find_map(|(kw, mode)| self.eat(kw).map(|_| mode));
+
+        if self.eat(Keyword::Fn).is_none() {
+            self.tokens = tokens;
+            return None;
+        }
+
+        let Some((_, parameter_tokens)) = self
+            .delimited('(')
+            .or_report_error(self, || format!("expected closure parameters"))
+        else {
+            return Some(self.add(ExprData::Error, self.span_consumed_since(start_span)));
+        };
+        let parameters = self.with_sub_parser(parameter_tokens, |sub_parser| {
+            sub_parser.parse_only_parameters()
+        });
+        let body = self.parse_required_block_expr("closure parameters");
+        let span = self.span_consumed_since(start_span);
+        let mode = mode.unwrap_or(CaptureMode::Share);
+        Some(self.add(ExprData::Closure(mode, parameters, body), span))
+    }
+
     fn parse_required_block_expr(&mut self, after: impl std::fmt::Display) -> Expr {
         self.parse_block_expr()
             .or_report_error(self, || format!("expected block after {after}"))
@@ -258,17 +646,6 @@ impl CodeParser<'_, '_> {
         Some(expr)
     }
 
-    fn parse_binop(&mut self, base: Expr, ops: &[Op]) -> Option<Expr> {
-        for &op in ops {
-            if let Some(_) = self.eat_op(op) {
-                let rhs = self.parse_required_expr(op);
-                let span = self.spans[base].to(self.spans[rhs]);
-                return Some(self.add(ExprData::Op(base, op, rhs), span));
-            }
-        }
-        None
-    }
-
     fn with_sub_parser<R>(
         &mut self,
         token_tree: TokenTree,
@@ -312,6 +689,34 @@ impl CodeParser<'_, '_> {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Associativity {
+    Left,
+
+    /// `a < b < c` is an error.
+    None,
+}
+
+/// Binary operators, grouped by precedence from loosest to tightest.
+/// Within a group, longer operators come first so that `<=` is not read as `<`.
+const BINARY_OPERATORS: &[(Associativity, &[Op])] = &[
+    (Associativity::Left, &[Op::OrOr]),
+    (Associativity::Left, &[Op::AndAnd]),
+    (
+        Associativity::None,
+        &[
+            Op::EqualEqual,
+            Op::NotEqual,
+            Op::LessEqual,
+            Op::GreaterEqual,
+            Op::LessThan,
+            Op::GreaterThan,
+        ],
+    ),
+    (Associativity::Left, &[Op::Plus, Op::Minus]),
+    (Associativity::Left, &[Op::Times, Op::DividedBy]),
+];
+
 trait OrDummyExpr {
     fn or_dummy_expr(self, parser: &mut CodeParser<'_, '_>) -> Expr;
 }
@@ -7,6 +7,8 @@ use dada_ir::{
     func::{Effect, Function},
     item::Item,
     kw::Keyword,
+    token::Token,
+    word::Word,
 };
 
 use super::OrReportError;
@@ -18,14 +20,21 @@ impl<'db> Parser<'db> {
             if let Some(item) = self.parse_item() {
                 items.push(item);
             } else {
-                let span = self.tokens.last_span();
-                self.tokens.consume();
+                let span = self.tokens.peek_span();
+                let token = self.tokens.consume();
+
+                // The lexer has already reported closing delimiters that were never opened.
+                if let Some(Token::Delimiter(')' | ']' | '}')) = token {
+                    continue;
+                }
+
                 dada_ir::diagnostic::Diagnostics::push(
                     self.db,
                     Diagnostic {
                         filename: self.filename(),
                         span,
                         message: format!("unexpected token"),
+                        labels: vec![],
                     },
                 );
             }
@@ -34,20 +43,25 @@ impl<'db> Parser<'db> {
     }
 
     fn parse_item(&mut self) -> Option<Item> {
-        if let Some(class) = self.parse_class() {
+        let docs = self.tokens.doc_comments();
+        if let Some(class) = self.parse_class(docs.clone()) {
             Some(Item::Class(class))
-        } else if let Some(function) = self.parse_function() {
+        } else if let Some(function) = self.parse_function(docs) {
             Some(Item::Function(function))
         } else {
             None
         }
     }
 
-    fn parse_class(&mut self) -> Option<Class> {
+    fn parse_class(&mut self, docs: Vec<Word>) -> Option<Class> {
         self.eat(Keyword::Class)?;
         let (class_name_span, class_name) = self
             .eat(Identifier)
             .or_report_error(self, || format!("expected a class name"))?;
+        let type_parameters = match self.delimited('[') {
+            Some((_, token_tree)) => Parser::new(self.db, token_tree).parse_only_type_parameters(),
+            None => vec![],
+        };
         let (_, field_tokens) = self
             .delimited('(')
             .or_report_error(self, || format!("expected class parameters"))?;
@@ -55,11 +69,13 @@ impl<'db> Parser<'db> {
             self.db,
             class_name,
             class_name_span,
+            type_parameters,
             field_tokens,
+            docs,
         ))
     }
 
-    fn parse_function(&mut self) -> Option<Function> {
+    fn parse_function(&mut self, docs: Vec<Word>) -> Option<Function> {
         let async_kw = self.eat(Keyword::Async);
         let effect = if async_kw.is_some() {
             Effect::Async
@@ -85,6 +101,7 @@ impl<'db> Parser<'db> {
             effect,
             argument_tokens,
             code,
+            docs,
         ))
     }
 }
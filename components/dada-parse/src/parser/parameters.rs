@@ -0,0 +1,232 @@
+use crate::{parser::Parser, token_test::Identifier};
+
+use dada_ir::{
+    class::Field,
+    func::Parameter,
+    kw::Keyword,
+    op::Op,
+    span::Span,
+    storage_mode::StorageMode,
+    ty::{Permission, Ty, TyData},
+    word::Word,
+};
+
+use super::OrReportError;
+
+/// A field or parameter, which share the same syntax.
+struct Declaration {
+    name: Word,
+    name_span: Span,
+
+    /// `None` if no storage mode was written.
+    mode: Option<StorageMode>,
+
+    ty: Option<Ty>,
+}
+
+impl Parser<'_> {
+    /// Parses the fields of a class (`var x, shared y, atomic z`); expects to consume
+    /// all available tokens (and errors if there are extra). Every field must
+    /// have a storage mode.
+    pub(crate) fn parse_only_fields(&mut self) -> Vec<Field> {
+        self.parse_only_declarations("field")
+            .into_iter()
+            .map(|d| {
+                let mode = d.mode.unwrap_or_else(|| {
+                    self.report_error(
+                        d.name_span,
+                        format!(
+                            "missing storage mode for field `{}`",
+                            d.name.as_str(self.db)
+                        ),
+                    );
+                    StorageMode::Shared
+                });
+                Field::new(self.db, d.name, d.name_span, mode, d.ty)
+            })
+            .collect()
+    }
+
+    /// Parses the parameters of a function (`var x, y: String`); expects to consume
+    /// all available tokens (and errors if there are extra). Parameters without
+    /// a storage mode are shared.
+    pub(crate) fn parse_only_parameters(&mut self) -> Vec<Parameter> {
+        self.parse_only_declarations("parameter")
+            .into_iter()
+            .map(|d| {
+                let mode = d.mode.unwrap_or(StorageMode::Shared);
+                Parameter::new(self.db, d.name, d.name_span, mode, d.ty)
+            })
+            .collect()
+    }
+
+    /// Parses a comma-separated list of declarations; `kind` is used in error messages.
+    fn parse_only_declarations(&mut self, kind: &str) -> Vec<Declaration> {
+        let mut declarations: Vec<Declaration> = vec![];
+        while self.tokens.peek().is_some() {
+            let Some(declaration) = self.parse_declaration(kind) else {
+                break;
+            };
+
+            if declarations.iter().any(|d| d.name == declaration.name) {
+                self.report_error(
+                    declaration.name_span,
+                    format!("duplicate {kind} `{}`", declaration.name.as_str(self.db)),
+                );
+            } else {
+                declarations.push(declaration);
+            }
+
+            if self.eat_op(Op::Comma).is_none() {
+                break;
+            }
+        }
+        self.report_error_if_more_tokens(format!("extra tokens after end of {kind}s"));
+        declarations
+    }
+
+    /// ```
+    /// Declaration := [StorageMode] Id [`:` Ty]
+    /// StorageMode := `shared` | `var` | `atomic`
+    /// ```
+    fn parse_declaration(&mut self, kind: &str) -> Option<Declaration> {
+        let mode = self.parse_storage_mode();
+        let (mut name_span, mut name) =
+            self.eat(Identifier).or_report_error(self, || match mode {
+                Some((kw, _)) => format!("expected {kind} name after `{kw}`"),
+                None => format!("expected {kind} name"),
+            })?;
+
+        let mut mode = mode.map(|(_, mode)| mode);
+
+        // Something like `mut x`: the first identifier was meant to be a storage mode.
+        if mode.is_none() {
+            if let Some((span, word)) = self.eat(Identifier) {
+                self.report_error(
+                    name_span,
+                    format!(
+                        "unknown storage mode `{}` (expected `shared`, `var`, or `atomic`)",
+                        name.as_str(self.db)
+                    ),
+                );
+                name_span = span;
+                name = word;
+
+                // The mode has been reported as unknown; don't also report it as missing.
+                mode = Some(StorageMode::Shared);
+            }
+        }
+
+        let ty = match self.eat_op(Op::Colon) {
+            Some(_) => self
+                .parse_ty()
+                .or_report_error(self, || format!("expected type after `:`")),
+            None => None,
+        };
+
+        Some(Declaration {
+            name,
+            name_span,
+            mode,
+            ty,
+        })
+    }
+
+    fn parse_storage_mode(&mut self) -> Option<(Keyword, StorageMode)> {
+        [
+            (Keyword::Shared, StorageMode::Shared),
+            (Keyword::Var, StorageMode::Var),
+            (Keyword::Atomic, StorageMode::Atomic),
+        ]
+        .into_iter()
+        .find_map(|(kw, mode)| self.eat(kw).map(|_| (kw, mode)))
+    }
+
+    /// ```
+    /// Ty := [Permission] Id [`[` Ty, ... `]`]
+    /// Permission := `my` | `our` | `shared` | `leased`
+    /// ```
+    fn parse_ty(&mut self) -> Option<Ty> {
+        if let Some(permission) = self.parse_permission() {
+            let ty = self
+                .parse_ty()
+                .or_report_error(self, || format!("expected type after `{permission}`"))?;
+            return Some(TyData::Permission(permission, ty).intern(self.db));
+        }
+
+        let (_, name) = self.eat(Identifier)?;
+        let arguments = match self.delimited('[') {
+            Some((_, token_tree)) => Parser::new(self.db, token_tree).parse_only_ty_arguments(),
+            None => vec![],
+        };
+        Some(TyData::Named(name, arguments).intern(self.db))
+    }
+
+    /// `my` and `our` are not keywords, so they are only treated as permissions
+    /// when followed by the rest of a type, as in `my Point`.
+    fn parse_permission(&mut self) -> Option<Permission> {
+        if self.eat(Keyword::Shared).is_some() {
+            return Some(Permission::Shared);
+        }
+        if self.eat(Keyword::Leased).is_some() {
+            return Some(Permission::Leased);
+        }
+
+        let tokens = self.tokens;
+        let (_, word) = self.eat(Identifier)?;
+        let permission = match word.as_str(self.db) {
+            "my" => Permission::My,
+            "our" => Permission::Our,
+            _ => {
+                self.tokens = tokens;
+                return None;
+            }
+        };
+        if self.peek(Identifier).is_none() {
+            self.tokens = tokens;
+            return None;
+        }
+        Some(permission)
+    }
+
+    /// Parses the type arguments in `Pair[String, Point]`; expects to consume
+    /// all available tokens (and errors if there are extra).
+    fn parse_only_ty_arguments(&mut self) -> Vec<Ty> {
+        let mut arguments = vec![];
+        while let Some(ty) = self
+            .parse_ty()
+            .or_report_error(self, || format!("expected type"))
+        {
+            arguments.push(ty);
+            if self.eat_op(Op::Comma).is_none() {
+                break;
+            }
+        }
+        self.report_error_if_more_tokens("extra tokens after end of type arguments");
+        arguments
+    }
+
+    /// Parses the type parameters of a class (`[K, V]`); expects to consume
+    /// all available tokens (and errors if there are extra).
+    pub(crate) fn parse_only_type_parameters(&mut self) -> Vec<Word> {
+        let mut parameters: Vec<Word> = vec![];
+        while let Some((span, name)) = self
+            .eat(Identifier)
+            .or_report_error(self, || format!("expected type parameter name"))
+        {
+            if parameters.contains(&name) {
+                self.report_error(
+                    span,
+                    format!("duplicate type parameter `{}`", name.as_str(self.db)),
+                );
+            } else {
+                parameters.push(name);
+            }
+            if self.eat_op(Op::Comma).is_none() {
+                break;
+            }
+        }
+        self.report_error_if_more_tokens("extra tokens after end of type parameters");
+        parameters
+    }
+}
@@ -1,11 +1,35 @@
-use dada_ir::{code::Ast, func::Function};
+use dada_ir::{
+    class::{Class, Field},
+    code::{Ast, Spans},
+    func::{Function, Parameter},
+};
 
 pub trait FunctionExt {
     fn ast(self, db: &dyn crate::Db) -> &Ast;
+    fn spans(self, db: &dyn crate::Db) -> &Spans;
+    fn parameters(self, db: &dyn crate::Db) -> &Vec<Parameter>;
 }
 
 impl FunctionExt for Function {
     fn ast(self, db: &dyn crate::Db) -> &Ast {
-        crate::parse_code(db, self.code(db))
+        crate::ast(db, self)
+    }
+
+    fn spans(self, db: &dyn crate::Db) -> &Spans {
+        crate::spans(db, self)
+    }
+
+    fn parameters(self, db: &dyn crate::Db) -> &Vec<Parameter> {
+        crate::parse_parameters(db, self)
+    }
+}
+
+pub trait ClassExt {
+    fn fields(self, db: &dyn crate::Db) -> &Vec<Field>;
+}
+
+impl ClassExt for Class {
+    fn fields(self, db: &dyn crate::Db) -> &Vec<Field> {
+        crate::parse_fields(db, self)
     }
 }
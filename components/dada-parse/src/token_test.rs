@@ -68,6 +68,32 @@ impl TokenTest for StringLiteral {
     }
 }
 
+/// A number literal like `22_000` or `2.5`; narrows to its text.
+pub(crate) struct NumberLiteral;
+impl TokenTest for NumberLiteral {
+    type Narrow = Word;
+
+    fn test(self, _db: &dyn crate::Db, token: Token) -> Option<Word> {
+        match token {
+            Token::Number(word) => Some(word),
+            _ => None,
+        }
+    }
+}
+
+/// A prefix like the `b` in `b"..."`; narrows to its text.
+pub(crate) struct Prefix;
+impl TokenTest for Prefix {
+    type Narrow = Word;
+
+    fn test(self, _db: &dyn crate::Db, token: Token) -> Option<Word> {
+        match token {
+            Token::Prefix(word) => Some(word),
+            _ => None,
+        }
+    }
+}
+
 pub(crate) struct FormatStringLiteral;
 impl TokenTest for FormatStringLiteral {
     type Narrow = FormatString;
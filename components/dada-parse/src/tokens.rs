@@ -1,4 +1,4 @@
-use dada_ir::{span::Span, token::Token, token_tree::TokenTree};
+use dada_ir::{span::Span, token::Token, token_tree::TokenTree, word::Word};
 
 #[derive(Copy, Clone)]
 pub(crate) struct Tokens<'me> {
@@ -7,6 +7,10 @@ pub(crate) struct Tokens<'me> {
     /// Span of last token consumed.
     last_span: Span,
     skipped: Skipped,
+
+    /// Whitespace and comments skipped after the last token consumed.
+    skipped_tokens: &'me [Token],
+
     tokens: &'me [Token],
 }
 
@@ -26,6 +30,7 @@ impl<'me> Tokens<'me> {
             last_span: start_span,
             tokens,
             skipped: Skipped::None,
+            skipped_tokens: &[],
         };
         this.skip_tokens();
         this
@@ -53,19 +58,38 @@ impl<'me> Tokens<'me> {
         self.skipped >= Skipped::Any
     }
 
+    /// Text of the doc comments skipped after consuming the last token,
+    /// without the leading `///`.
+    pub(crate) fn doc_comments(&self) -> Vec<Word> {
+        self.skipped_tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::DocComment(text) => {
+                    let text = &text.as_str(self.db)["///".len()..];
+                    Some(Word::from(self.db, text.strip_prefix(' ').unwrap_or(text)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Skip tokens that the parser doesn't want to see,
-    /// such as whitespace.
+    /// such as whitespace and comments.
     fn skip_tokens(&mut self) {
         self.skipped = Skipped::None;
+        let tokens = self.tokens;
         while let Some(t) = self.peek() {
             match t {
                 Token::Whitespace('\n') => self.skipped = self.skipped.max(Skipped::Newline),
-                Token::Whitespace(_) => self.skipped = self.skipped.max(Skipped::Any),
-                _ => return,
+                Token::Whitespace(_) | Token::Comment(_) | Token::DocComment(_) => {
+                    self.skipped = self.skipped.max(Skipped::Any)
+                }
+                _ => break,
             }
 
             self.next_token();
         }
+        self.skipped_tokens = &tokens[..tokens.len() - self.tokens.len()];
     }
 
     /// Advance by one token and return the span + token just consumed (if any).
@@ -0,0 +1,77 @@
+//! Checks that an edit which only changes whitespace in a function does not
+//! re-execute the queries that depend on its `Ast` alone.
+
+#![allow(incomplete_features)]
+#![feature(trait_upcasting)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dada_ir::{code::Block, func::Function, item::Item, word::Word};
+use dada_parse::prelude::*;
+
+#[salsa::jar(Db)]
+struct Jar(body_block);
+
+trait Db: salsa::DbWithJar<Jar> + dada_parse::Db {}
+impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_parse::Db {}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// A query that reads only the `Ast` of `function`.
+#[salsa::memoized(in crate::Jar)]
+fn body_block(db: &dyn crate::Db, function: Function) -> Block {
+    EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    function.ast(db).block
+}
+
+#[salsa::db(
+    crate::Jar,
+    dada_ir::Jar,
+    dada_lex::Jar,
+    dada_manifest::Jar,
+    dada_parse::Jar
+)]
+#[derive(Default)]
+struct TestDb {
+    storage: salsa::Storage<Self>,
+}
+
+impl salsa::Database for TestDb {
+    fn salsa_runtime(&self) -> &salsa::Runtime {
+        self.storage.runtime()
+    }
+}
+
+/// Sets the text of `filename` and runs `body_block` on its first function.
+fn edit(db: &mut TestDb, filename: Word, source_text: &str) {
+    dada_manifest::source_text::set(db, filename, source_text.to_string());
+    let function = dada_parse::parse_file(db, filename)
+        .iter()
+        .find_map(|item| match *item {
+            Item::Function(function) => Some(function),
+            Item::Class(_) => None,
+        })
+        .unwrap();
+    body_block(db, function);
+}
+
+#[test]
+fn whitespace_edit_keeps_ast_queries() {
+    let mut db = TestDb::default();
+    let filename = Word::from(&db, "incremental.dada");
+
+    edit(&mut db, filename, "fn main() {\n    print(m: \"hi\")\n}\n");
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 1);
+
+    // Only the spans move, so the `Ast` is equal and `body_block` is reused.
+    edit(
+        &mut db,
+        filename,
+        "fn main() {\n\n        print(m:   \"hi\")\n}\n",
+    );
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 1);
+
+    // A change to the code itself does re-execute it.
+    edit(&mut db, filename, "fn main() {\n    print(m: \"bye\")\n}\n");
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 2);
+}
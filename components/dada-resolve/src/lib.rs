@@ -0,0 +1,12 @@
+#![allow(incomplete_features)]
+#![feature(trait_upcasting)]
+
+mod resolve;
+
+#[salsa::jar(Db)]
+pub struct Jar(resolve::resolve_names);
+
+pub trait Db: salsa::DbWithJar<Jar> + dada_ir::Db + dada_lex::Db + dada_parse::Db {}
+impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_ir::Db + dada_lex::Db + dada_parse::Db {}
+
+pub use resolve::resolve_names;
@@ -0,0 +1,170 @@
+use dada_ir::{
+    code::{Ast, Block, Expr, ExprData},
+    definition::{Definition, Resolutions},
+    func::Function,
+    intrinsic::Intrinsic,
+    item::Item,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+/// Resolves each name used in the body of `function` to its definition,
+/// recording the names that refer to nothing. They are reported by the
+/// checks instead of here, so that this query does not depend on spans and
+/// its result survives edits that only move code around.
+///
+/// Names are looked up in the enclosing blocks, innermost first; a variable
+/// is in scope from its declaration to the end of its block, and shadows
+/// earlier variables and parameters with the same name. Names that are not
+/// variables refer to the items in the same file or to intrinsics.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn resolve_names(db: &dyn crate::Db, function: Function) -> Resolutions {
+    let filename = function.code(db).tokens(db).filename(db);
+    let ast = function.ast(db);
+
+    // The body shares a scope with the parameters, as when it is executed.
+    let parameters = function
+        .parameters(db)
+        .iter()
+        .map(|&p| (p.name(db), Definition::Parameter(p)))
+        .collect();
+    let mut resolver = Resolver {
+        db,
+        ast,
+        items: dada_parse::parse_file(db, filename),
+        scopes: vec![parameters],
+        resolutions: Resolutions::default(),
+    };
+    resolver.resolve_block_contents(ast.block);
+    resolver.resolutions
+}
+
+struct Resolver<'me> {
+    db: &'me dyn crate::Db,
+    ast: &'me Ast,
+    items: &'me [Item],
+
+    /// The variables declared in each enclosing block (innermost last),
+    /// in declaration order.
+    scopes: Vec<Vec<(Word, Definition)>>,
+
+    resolutions: Resolutions,
+}
+
+impl Resolver<'_> {
+    fn resolve_block_contents(&mut self, block: Block) {
+        for &expr in &self.ast.tables[block].exprs {
+            self.resolve(expr);
+        }
+    }
+
+    fn resolve(&mut self, expr: Expr) {
+        match &self.ast.tables[expr] {
+            ExprData::Id(name) => match self.lookup(*name) {
+                Some(definition) => {
+                    self.resolutions.definitions.insert(expr, definition);
+                }
+                None => self.resolutions.unresolved.push(expr),
+            },
+
+            ExprData::Var(_, name, initializer) => {
+                // The initializer cannot refer to the variable being declared.
+                self.resolve(*initializer);
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .push((*name, Definition::Local(expr)));
+            }
+
+            ExprData::Block(block) => {
+                self.scopes.push(vec![]);
+                self.resolve_block_contents(*block);
+                self.scopes.pop();
+            }
+
+            ExprData::Closure(_, parameters, body) => {
+                let parameters = parameters
+                    .iter()
+                    .map(|&p| (p.name(self.db), Definition::Parameter(p)))
+                    .collect();
+                self.scopes.push(parameters);
+                self.resolve(*body);
+                self.scopes.pop();
+            }
+
+            ExprData::StringLiteral(_)
+            | ExprData::NumberLiteral(_)
+            | ExprData::Continue
+            | ExprData::Error => {}
+
+            ExprData::Dot(expr, _)
+            | ExprData::Await(expr)
+            | ExprData::Share(expr)
+            | ExprData::Lease(expr)
+            | ExprData::Give(expr)
+            | ExprData::Parenthesized(expr)
+            | ExprData::Loop(expr)
+            | ExprData::Atomic(expr)
+            | ExprData::Unary(_, expr) => self.resolve(*expr),
+
+            ExprData::Break(expr) | ExprData::Return(expr) => {
+                if let Some(expr) = *expr {
+                    self.resolve(expr);
+                }
+            }
+
+            ExprData::Concatenate(exprs) | ExprData::Tuple(exprs) | ExprData::List(exprs) => {
+                for &expr in exprs {
+                    self.resolve(expr);
+                }
+            }
+
+            ExprData::Call(callee, arguments) => {
+                self.resolve(*callee);
+                for &argument in arguments {
+                    self.resolve(self.ast.tables[argument].expr);
+                }
+            }
+
+            ExprData::If(condition, then_expr, else_expr) => {
+                self.resolve(*condition);
+                self.resolve(*then_expr);
+                if let Some(else_expr) = *else_expr {
+                    self.resolve(else_expr);
+                }
+            }
+
+            ExprData::While(lhs, rhs)
+            | ExprData::Index(lhs, rhs)
+            | ExprData::Op(lhs, _, rhs)
+            | ExprData::OpEq(lhs, _, rhs)
+            | ExprData::Assign(lhs, rhs) => {
+                self.resolve(*lhs);
+                self.resolve(*rhs);
+            }
+        }
+    }
+
+    /// Finds the definition that `name` refers to at the current point.
+    fn lookup(&self, name: Word) -> Option<Definition> {
+        let variable = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, definition)| definition);
+        if variable.is_some() {
+            return variable;
+        }
+
+        let item = self.items.iter().find(|item| item.name(self.db) == name);
+        match item {
+            Some(&Item::Class(class)) => return Some(Definition::Class(class)),
+            Some(&Item::Function(function)) => return Some(Definition::Function(function)),
+            None => {}
+        }
+
+        Intrinsic::from_word(self.db, name).map(Definition::Intrinsic)
+    }
+}
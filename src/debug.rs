@@ -0,0 +1,209 @@
+//! `dada debug`: runs a program one expression at a time, showing the
+//! local variables and the heap graph whenever execution stops.
+
+use std::{collections::BTreeSet, io::BufRead, ops::ControlFlow, path::PathBuf};
+
+use dada_execute::{HeapGraphFormat, Observer, Stop};
+use dada_ir::{
+    span::{Offset, Span},
+    word::Word,
+};
+use eyre::Context;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    path: PathBuf,
+
+    /// Stop before executing the first statement that starts on or after this line.
+    /// If no breakpoints are given, execution stops at the first statement.
+    #[structopt(long = "break", short = "b")]
+    breakpoints: Vec<u32>,
+
+    /// Format of the heap graph printed at each stop (`box` or `dot`).
+    #[structopt(long, default_value = "box")]
+    heap_graph: HeapGraphFormat,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let (db, filename, contents) = crate::run::load(&self.path)?;
+
+        let breakpoints = self
+            .breakpoints
+            .iter()
+            .map(|&line| resolve_breakpoint(&db, filename, line))
+            .collect::<eyre::Result<_>>()?;
+
+        let mode = if self.breakpoints.is_empty() {
+            Mode::StepInto
+        } else {
+            Mode::Continue
+        };
+        let mut debugger = Debugger {
+            db: &db,
+            filename,
+            source_lines: contents.lines().map(|line| line.to_string()).collect(),
+            breakpoints,
+            statement: None,
+            mode,
+            format: self.heap_graph,
+        };
+        dada_execute::interpret_with_observer(&db, filename, &mut std::io::stdout(), &mut debugger)
+            .with_context(|| format!("executing `{}`", self.path.display()))
+    }
+}
+
+const HELP: &str = "\
+commands:
+  s, step           run until the next expression, entering subexpressions and calls
+  n, next           run until the current expression has been evaluated
+  c, continue       run until the next breakpoint
+  b, break <line>   stop before the first statement on or after <line>
+  clear <line>      remove the breakpoint set by `break <line>`
+  l, locals         print the local variables
+  h, heap [format]  print the heap graph (`box` or `dot`)
+  q, quit           stop debugging";
+
+struct Debugger<'db> {
+    db: &'db dada_db::Db,
+    filename: Word,
+    source_lines: Vec<String>,
+
+    /// Start offsets of the statements to stop before;
+    /// see [`dada_execute::breakpoint_statement`].
+    breakpoints: BTreeSet<Offset>,
+
+    /// The statement that is about to be evaluated, until its
+    /// `before_expr` notification arrives.
+    statement: Option<Span>,
+
+    mode: Mode,
+    format: HeapGraphFormat,
+}
+
+/// What the debugger does when the next expression is reached.
+enum Mode {
+    /// Stop at the next expression.
+    StepInto,
+
+    /// Stop at the next expression that is not nested within the one that
+    /// was stopped at, i.e. whose `(depth, nesting)` is at most the given one.
+    StepOver { depth: usize, nesting: usize },
+
+    /// Stop only at breakpoints.
+    Continue,
+}
+
+impl Observer for Debugger<'_> {
+    fn before_statement(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        self.statement = Some(stop.span);
+        ControlFlow::Continue(())
+    }
+
+    fn before_expr(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let at_breakpoint =
+            self.statement.take() == Some(stop.span) && self.breakpoints.contains(&stop.span.start);
+        let stepped = match self.mode {
+            Mode::StepInto => true,
+            Mode::StepOver { depth, nesting } => (stop.depth, stop.nesting) <= (depth, nesting),
+            Mode::Continue => false,
+        };
+        if stepped || at_breakpoint {
+            self.stopped(stop)
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+impl Debugger<'_> {
+    /// Shows where execution stopped and then reads commands until one resumes
+    /// it; breaks if the user quits.
+    fn stopped(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let position = dada_lex::line_column(self.db, self.filename, stop.span.start);
+        eprintln!(
+            "stopped in `{}` at {}:{}:{}",
+            stop.function.name(self.db).as_str(self.db),
+            self.filename.as_str(self.db),
+            position.line,
+            position.column,
+        );
+        if let Some(text) = self.source_lines.get(position.line as usize - 1) {
+            eprintln!("{:>4} | {}", position.line, text);
+        }
+        eprintln!("{}", stop.heap_graph().render(self.format));
+
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            eprint!("(dada) ");
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(_)) | None => return ControlFlow::Break(()),
+            };
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (None, _) => {}
+                (Some("s" | "step"), None) => {
+                    self.mode = Mode::StepInto;
+                    return ControlFlow::Continue(());
+                }
+                (Some("n" | "next"), None) => {
+                    self.mode = Mode::StepOver {
+                        depth: stop.depth,
+                        nesting: stop.nesting,
+                    };
+                    return ControlFlow::Continue(());
+                }
+                (Some("c" | "continue"), None) => {
+                    self.mode = Mode::Continue;
+                    return ControlFlow::Continue(());
+                }
+                (Some("b" | "break"), Some(line)) => match self.breakpoint(line) {
+                    Ok(start) => {
+                        self.breakpoints.insert(start);
+                        let line = dada_lex::line_column(self.db, self.filename, start).line;
+                        eprintln!("breakpoint set on line {line}");
+                    }
+                    Err(e) => eprintln!("{e}"),
+                },
+                (Some("clear"), Some(line)) => match self.breakpoint(line) {
+                    Ok(start) => {
+                        if !self.breakpoints.remove(&start) {
+                            eprintln!("no breakpoint on line {line}");
+                        }
+                    }
+                    Err(e) => eprintln!("{e}"),
+                },
+                (Some("l" | "locals"), None) => {
+                    for entry in stop.heap_graph().locals() {
+                        eprintln!("{} = {}", entry.name, entry.value);
+                    }
+                }
+                (Some("h" | "heap"), format) => {
+                    match format.map(str::parse).unwrap_or(Ok(self.format)) {
+                        Ok(format) => eprintln!("{}", stop.heap_graph().render(format)),
+                        Err(e) => eprintln!("{e}"),
+                    }
+                }
+                (Some("q" | "quit"), None) => return ControlFlow::Break(()),
+                _ => eprintln!("{HELP}"),
+            }
+        }
+    }
+
+    /// Parses the line number `line` and resolves it with [`resolve_breakpoint`].
+    fn breakpoint(&self, line: &str) -> eyre::Result<Offset> {
+        let line = line
+            .parse()
+            .map_err(|_| eyre::eyre!("expected a line number, found `{line}`"))?;
+        resolve_breakpoint(self.db, self.filename, line)
+    }
+}
+
+/// Returns the start of the statement that a breakpoint on `line` stops at.
+fn resolve_breakpoint(db: &dada_db::Db, filename: Word, line: u32) -> eyre::Result<Offset> {
+    dada_execute::breakpoint_statement(db, filename, line)
+        .map(|span| span.start)
+        .ok_or_else(|| eyre::eyre!("no statement on or after line {line}"))
+}
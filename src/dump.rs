@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use dada_ir::item::Item;
+use eyre::Context;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    path: PathBuf,
+
+    /// Print the control-flow graph of each function.
+    #[structopt(long)]
+    cfg: bool,
+
+    /// Print graphs in Graphviz `dot` format rather than as text.
+    #[structopt(long)]
+    dot: bool,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        if !self.cfg {
+            eyre::bail!("nothing to dump; try `--cfg`");
+        }
+
+        let mut db = dada_db::Db::default();
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("reading `{}`", self.path.display()))?;
+        let filename = dada_ir::word::Word::from(&db, &self.path);
+        db.update_file(filename, contents);
+
+        for item in db.items(filename) {
+            if let Item::Function(function) = item {
+                if self.dot {
+                    println!("{}", dada_lower::dump_cfg_graphviz(&db, function));
+                } else {
+                    println!("{}", dada_lower::dump_cfg(&db, function));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -3,7 +3,12 @@ use std::ffi::OsString;
 use structopt::StructOpt;
 
 mod check;
+mod dap;
+mod debug;
+mod dump;
 mod ide;
+mod repl;
+mod run;
 mod test_harness;
 
 #[derive(StructOpt)]
@@ -25,8 +30,15 @@ impl Options {
             Command::Ide(command_options) => {
                 ide::main(self, command_options)?;
             }
+            Command::Dap(command_options) => {
+                dap::main(self, command_options)?;
+            }
             Command::Check(command_options) => command_options.main(self)?,
+            Command::Run(command_options) => command_options.main(self)?,
+            Command::Debug(command_options) => command_options.main(self)?,
+            Command::Dump(command_options) => command_options.main(self)?,
             Command::Test(command_options) => command_options.main(self)?,
+            Command::Repl(command_options) => command_options.main(self)?,
         }
         Ok(())
     }
@@ -36,6 +48,15 @@ impl Options {
 pub enum Command {
     /// Pound acorns into flour for cookie dough.
     Ide(ide::Options),
+    /// Serve the Debug Adapter Protocol over stdin/stdout.
+    Dap(dap::Options),
     Check(check::Options),
+    Run(run::Options),
+    /// Run a program under a step debugger.
+    Debug(debug::Options),
+    /// Print the compiler's intermediate representations of a program.
+    Dump(dump::Options),
     Test(test_harness::Options),
+    /// Evaluate definitions and expressions interactively.
+    Repl(repl::Options),
 }
@@ -0,0 +1,169 @@
+//! `dada repl`: reads class and function definitions or expressions, one
+//! snippet at a time. Definitions are remembered; expressions are evaluated
+//! and their value printed.
+//!
+//! Everything entered so far is kept in a synthetic file, with the
+//! definitions first and the expression being evaluated wrapped in a function
+//! named [`ENTRY`] at the end.
+
+use std::io::{BufRead, Write};
+
+use dada_ir::{token::Token, token_tree::TokenTree, word::Word};
+
+/// Name of the function that wraps the expression being evaluated.
+/// Definitions may not use it, so that they cannot clash with the wrapper.
+const ENTRY: &str = "__repl_main";
+
+#[derive(structopt::StructOpt)]
+pub struct Options {}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut repl = Repl::new();
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+        loop {
+            eprint!("{}", if buffer.is_empty() { "dada> " } else { "  ... " });
+            std::io::stderr().flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            buffer.push_str(&line);
+            buffer.push('\n');
+
+            // Keep reading until every `(`, `[`, and `{` has been closed.
+            if repl.is_incomplete(&buffer) {
+                continue;
+            }
+            let snippet = std::mem::take(&mut buffer);
+            if !snippet.trim().is_empty() {
+                repl.snippet(snippet);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Repl {
+    db: dada_db::Db,
+
+    /// Holds the definitions followed by the `main` function being evaluated.
+    program: Word,
+
+    /// Holds the snippet most recently entered, on its own.
+    input: Word,
+
+    /// Source of each definition entered so far, along with the names it defines.
+    definitions: Vec<(Vec<Word>, String)>,
+}
+
+impl Repl {
+    fn new() -> Self {
+        let db = dada_db::Db::default();
+        let program = Word::from(&db, "<repl>");
+        let input = Word::from(&db, "<repl-input>");
+        Self {
+            db,
+            program,
+            input,
+            definitions: vec![],
+        }
+    }
+
+    /// True if `text` has an unclosed delimiter.
+    fn is_incomplete(&mut self, text: &str) -> bool {
+        self.db.update_file(self.input, text.to_string());
+        let tree = dada_lex::lex_file(&self.db, self.input);
+        has_unclosed_delimiter(&self.db, tree)
+    }
+
+    fn snippet(&mut self, snippet: String) {
+        // Look past comments, which may document a definition that follows.
+        let trimmed = snippet
+            .lines()
+            .map(str::trim_start)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+            .unwrap_or_default();
+        if ["class ", "fn ", "async fn "]
+            .iter()
+            .any(|keyword| trimmed.starts_with(keyword))
+        {
+            self.define(snippet);
+        } else {
+            self.evaluate(&snippet);
+        }
+    }
+
+    /// Adds the definitions in `snippet`, replacing earlier definitions with the same names.
+    fn define(&mut self, snippet: String) {
+        self.db.update_file(self.input, snippet.clone());
+        let names: Vec<Word> = self
+            .db
+            .items(self.input)
+            .iter()
+            .map(|item| item.name(&self.db))
+            .collect();
+        if names.iter().any(|name| name.as_str(&self.db) == ENTRY) {
+            eprintln!("`{ENTRY}` is reserved by the REPL");
+            return;
+        }
+
+        let mut definitions = self.definitions.clone();
+        definitions.retain(|(defined, _)| !defined.iter().any(|name| names.contains(name)));
+        definitions.push((names, snippet));
+
+        let source = definitions_source(&definitions);
+        if self.check(source) {
+            self.definitions = definitions;
+        }
+    }
+
+    /// Evaluates the expression `snippet` and prints its value, unless it is `()`.
+    fn evaluate(&mut self, snippet: &str) {
+        let source = format!(
+            "{}async fn {ENTRY}() {{\n{}\n}}\n",
+            definitions_source(&self.definitions),
+            snippet.trim_end(),
+        );
+        if !self.check(source) {
+            return;
+        }
+        let result =
+            dada_execute::interpret_entry(&self.db, self.program, ENTRY, &mut std::io::stdout());
+        match result {
+            Ok(Some(value)) => println!("{value}"),
+            Ok(None) => {}
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+
+    /// Makes `source` the contents of the program, reporting any compilation errors.
+    /// Returns true if there were none.
+    fn check(&mut self, source: String) -> bool {
+        self.db.update_file(self.program, source);
+        let diagnostics = self.db.diagnostics(self.program);
+        for diagnostic in &diagnostics {
+            eprintln!("{}", dada_lex::format_diagnostic(&self.db, diagnostic));
+        }
+        diagnostics.is_empty()
+    }
+}
+
+fn definitions_source(definitions: &[(Vec<Word>, String)]) -> String {
+    definitions.iter().map(|(_, source)| source.as_str()).collect()
+}
+
+/// The lexer reads everything after an opening delimiter into a nested tree,
+/// which is followed by the closing delimiter unless the input ended first.
+fn has_unclosed_delimiter(db: &dada_db::Db, tree: TokenTree) -> bool {
+    let tokens = tree.tokens(db);
+    tokens.iter().enumerate().any(|(index, token)| match token {
+        Token::Tree(subtree) => {
+            !matches!(tokens.get(index + 1), Some(Token::Delimiter(')' | ']' | '}')))
+                || has_unclosed_delimiter(db, *subtree)
+        }
+        _ => false,
+    })
+}
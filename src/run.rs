@@ -0,0 +1,72 @@
+use std::{
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+use dada_execute::{HeapGraphFormat, Observer, Stop};
+use dada_ir::word::Word;
+use eyre::Context;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    path: PathBuf,
+
+    /// Print the heap graph of `main` just before it returns, either as
+    /// a box diagram (`box`) or in Graphviz format (`dot`).
+    #[structopt(long)]
+    heap_graph: Option<HeapGraphFormat>,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let (db, filename, _) = load(&self.path)?;
+
+        let mut observer = HeapGraphPrinter {
+            db: &db,
+            format: self.heap_graph,
+        };
+        dada_execute::interpret_with_observer(&db, filename, &mut std::io::stdout(), &mut observer)
+            .with_context(|| format!("executing `{}`", self.path.display()))
+    }
+}
+
+/// Reads and compiles the file at `path`, failing if it has any diagnostics.
+/// Returns the database along with the file's name and contents.
+pub(crate) fn load(path: &Path) -> eyre::Result<(dada_db::Db, Word, String)> {
+    let mut db = dada_db::Db::default();
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("reading `{}`", path.display()))?;
+    let filename = Word::from(&db, path);
+    db.update_file(filename, contents.clone());
+
+    let diagnostics = db.diagnostics(filename);
+    if !diagnostics.is_empty() {
+        for diagnostic in &diagnostics {
+            eprintln!("{}", dada_lex::format_diagnostic(&db, diagnostic));
+        }
+        eyre::bail!(
+            "not executing `{}` due to {} compilation error(s)",
+            path.display(),
+            diagnostics.len()
+        );
+    }
+
+    Ok((db, filename, contents))
+}
+
+/// Prints the heap graph when `main` exits, if requested.
+struct HeapGraphPrinter<'db> {
+    db: &'db dada_db::Db,
+    format: Option<HeapGraphFormat>,
+}
+
+impl Observer for HeapGraphPrinter<'_> {
+    fn function_exit(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        if let Some(format) = self.format {
+            if stop.function.name(self.db).as_str(self.db) == "main" {
+                eprintln!("{}", stop.heap_graph().render(format));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
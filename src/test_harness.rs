@@ -1,7 +1,14 @@
-use std::path::{Path, PathBuf};
-
+use std::{
+    cell::RefCell,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use dada_execute::{HeapGraphFormat, Observer, Stop};
+use dada_ir::{item::Item, word::Word};
 use eyre::Context;
-use lsp_types::Diagnostic;
+use lsp_types::{Diagnostic, DiagnosticRelatedInformation};
 use regex::Regex;
 
 mod lsp_client;
@@ -90,7 +97,10 @@ impl Options {
         // Second, compare the full details to the `.ref` file.
         // If we are in DADA_BLESS mode, then update the `.ref` file.
         let ref_path = path.with_extension("ref");
-        let actual_diagnostics = format!("{:#?}", diagnostics);
+        let actual_diagnostics = format!(
+            "{:#?}",
+            diagnostics.iter().map(RefDiagnostic).collect::<Vec<_>>()
+        );
         self.maybe_bless_file(&ref_path, &actual_diagnostics)?;
         let ref_contents = std::fs::read_to_string(&ref_path)
             .with_context(|| format!("reading `{}`", ref_path.display()))?;
@@ -101,6 +111,24 @@ impl Options {
             });
         }
 
+        // Third, if the program compiled and has a `main` function, run it and
+        // compare what it printed (and the error it failed with, if any) to the
+        // `.stdout` file.
+        if diagnostics.is_empty() {
+            if let Some(actual_output) = run_dada_file(path)? {
+                let stdout_path = path.with_extension("stdout");
+                self.maybe_bless_file(&stdout_path, &actual_output)?;
+                let stdout_contents = std::fs::read_to_string(&stdout_path)
+                    .with_context(|| format!("reading `{}`", stdout_path.display()))?;
+                if stdout_contents != actual_output {
+                    errors.push(RefOutputDoesNotMatch {
+                        expected: stdout_contents,
+                        actual: actual_output,
+                    });
+                }
+            }
+        }
+
         errors.into_result()
     }
 
@@ -114,6 +142,149 @@ impl Options {
     }
 }
 
+/// Runs the `main` function of the program at `path`, returning everything
+/// that it printed followed by the error that stopped it, if any.
+/// Returns `None` if the program has no `main` function.
+///
+/// Each `//? heap` line in the program adds the heap graph of the function
+/// containing it to the output, as it was after the lines above it ran.
+fn run_dada_file(path: &Path) -> eyre::Result<Option<String>> {
+    let mut db = dada_db::Db::default();
+    let contents = std::fs::read_to_string(path)?;
+    let filename = Word::from(&db, path);
+    db.update_file(filename, contents.clone());
+
+    let has_main = db.items(filename).iter().any(|&item| match item {
+        Item::Function(function) => function.name(&db).as_str(&db) == "main",
+        Item::Class(_) => false,
+    });
+    if !has_main {
+        return Ok(None);
+    }
+
+    let re = Regex::new(r"^\s*//\? heap\s*$").unwrap();
+    let output = SharedOutput::default();
+    let mut observer = HeapGraphMarkers {
+        db: &db,
+        filename,
+        lines: (1..)
+            .zip(contents.lines())
+            .filter(|(_, line)| re.is_match(line))
+            .map(|(line_number, _)| line_number)
+            .collect(),
+        output: output.clone(),
+    };
+    let result =
+        dada_execute::interpret_with_observer(&db, filename, &mut output.clone(), &mut observer);
+    let mut output = String::from_utf8(output.0.take())?;
+    if let Err(error) = result {
+        output.push_str(&format!("{error}\n"));
+    }
+    Ok(Some(output))
+}
+
+/// Collects what a program prints along with the heap graphs taken while
+/// it runs, in the order in which they were produced.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl std::io::Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Takes a heap graph for each `//? heap` marker once execution has passed
+/// the lines before it: just before the next statement in the function
+/// containing the marker starts, or else when that function returns.
+struct HeapGraphMarkers<'db> {
+    db: &'db dada_db::Db,
+    filename: Word,
+
+    /// Lines of the markers that have not been reached yet.
+    lines: Vec<u32>,
+
+    output: SharedOutput,
+}
+
+impl HeapGraphMarkers<'_> {
+    /// Takes the heap graph for each marker in `stop.function` that comes
+    /// before `line`.
+    fn take_heap_graphs(&mut self, stop: &Stop<'_>, line: u32) {
+        let body = stop.function.code(self.db).tokens(self.db).span(self.db);
+        let first_line = dada_lex::line_column(self.db, self.filename, body.start).line;
+        let last_line = dada_lex::line_column(self.db, self.filename, body.end).line;
+        let (reached, pending): (Vec<u32>, Vec<u32>) = self
+            .lines
+            .iter()
+            .partition(|&&marker| first_line <= marker && marker <= last_line && marker < line);
+        self.lines = pending;
+
+        for marker in reached {
+            let graph = stop.heap_graph().render(HeapGraphFormat::Box);
+            let text = format!("heap graph at line {marker}:\n{graph}\n");
+            self.output
+                .0
+                .borrow_mut()
+                .extend_from_slice(text.as_bytes());
+        }
+    }
+}
+
+impl Observer for HeapGraphMarkers<'_> {
+    fn before_statement(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        let line = dada_lex::line_column(self.db, self.filename, stop.span.start).line;
+        self.take_heap_graphs(stop, line);
+        ControlFlow::Continue(())
+    }
+
+    fn function_exit(&mut self, stop: &Stop<'_>) -> ControlFlow<()> {
+        self.take_heap_graphs(stop, u32::MAX);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Formats a diagnostic for a `.ref` file. This is its `Debug` output, except
+/// that related information leaves out the URI of the file, which depends on
+/// where the tests are run.
+struct RefDiagnostic<'a>(&'a Diagnostic);
+
+impl std::fmt::Debug for RefDiagnostic<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = self.0;
+        f.debug_struct("Diagnostic")
+            .field("range", &d.range)
+            .field("severity", &d.severity)
+            .field("code", &d.code)
+            .field("source", &d.source)
+            .field("message", &d.message)
+            .field(
+                "related_information",
+                &d.related_information
+                    .as_ref()
+                    .map(|infos| infos.iter().map(RefRelatedInformation).collect::<Vec<_>>()),
+            )
+            .field("tags", &d.tags)
+            .finish()
+    }
+}
+
+struct RefRelatedInformation<'a>(&'a DiagnosticRelatedInformation);
+
+impl std::fmt::Debug for RefRelatedInformation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiagnosticRelatedInformation")
+            .field("range", &self.0.location.range)
+            .field("message", &self.0.message)
+            .finish()
+    }
+}
+
 #[derive(Debug, Default)]
 struct Errors {
     reports: Vec<eyre::Report>,
@@ -0,0 +1,196 @@
+//! Drives `dada dap` through a scripted debugging session.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+/// A running `dada dap`, along with the events it has sent so far.
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    seq: i64,
+    events: Vec<Value>,
+}
+
+impl Session {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_dada"))
+            .arg("dap")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Session {
+            child,
+            stdin,
+            stdout,
+            seq: 0,
+            events: vec![],
+        }
+    }
+
+    /// Sends a request and returns the response to it, keeping the events
+    /// that arrive in the meantime.
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let body = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+
+        loop {
+            let message = self.receive();
+            if message["type"] == "response" && message["request_seq"] == self.seq {
+                return message;
+            }
+            if message["type"] == "event" {
+                self.events.push(message);
+            }
+        }
+    }
+
+    /// Returns the body of the next `event` event, waiting for it if needed.
+    fn event(&mut self, event: &str) -> Value {
+        loop {
+            if let Some(index) = self.events.iter().position(|e| e["event"] == event) {
+                let mut message = self.events.remove(index);
+                return message["body"].take();
+            }
+            let message = self.receive();
+            if message["type"] == "event" {
+                self.events.push(message);
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut content_length = None;
+        loop {
+            let mut header = String::new();
+            assert_ne!(
+                self.stdout.read_line(&mut header).unwrap(),
+                0,
+                "unexpected EOF"
+            );
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(length) = header.strip_prefix("Content-Length:") {
+                content_length = Some(length.trim().parse::<usize>().unwrap());
+            }
+        }
+        let mut body = vec![0; content_length.unwrap()];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// What the program printed, according to the `output` events received so far.
+    fn stdout(&self) -> String {
+        self.events
+            .iter()
+            .filter(|e| e["event"] == "output" && e["body"]["category"] == "stdout")
+            .map(|e| e["body"]["output"].as_str().unwrap())
+            .collect()
+    }
+}
+
+#[test]
+fn breakpoint_session() {
+    let path = std::env::temp_dir().join("dada-dap-test-breakpoint_session.dada");
+    std::fs::write(
+        &path,
+        "\
+class Point(var x, var y)
+
+async fn main() {
+    p = Point(x: 22, y: 44)
+    n = 1
+    print(m: \"{p.x}\").await
+}
+",
+    )
+    .unwrap();
+    let path = path.display().to_string();
+
+    let mut session = Session::start();
+    let response = session.request("initialize", json!({ "adapterID": "dada" }));
+    assert_eq!(response["success"], true);
+    session.event("initialized");
+
+    let response = session.request("launch", json!({ "program": path }));
+    assert_eq!(response["success"], true);
+
+    let response = session.request(
+        "setBreakpoints",
+        json!({ "source": { "path": path }, "breakpoints": [{ "line": 6 }] }),
+    );
+    assert_eq!(
+        response["body"]["breakpoints"],
+        json!([{ "verified": true, "line": 6 }])
+    );
+
+    let response = session.request("configurationDone", json!({}));
+    assert_eq!(response["success"], true);
+    let stopped = session.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+
+    let response = session.request("stackTrace", json!({ "threadId": 1 }));
+    let frames = &response["body"]["stackFrames"];
+    assert_eq!(frames.as_array().unwrap().len(), 1);
+    assert_eq!(frames[0]["name"], "main");
+    assert_eq!(frames[0]["line"], 6);
+    assert_eq!(frames[0]["column"], 5);
+
+    let response = session.request("scopes", json!({ "frameId": frames[0]["id"] }));
+    let locals = response["body"]["scopes"][0]["variablesReference"].clone();
+    let response = session.request("variables", json!({ "variablesReference": locals }));
+    let variables = response["body"]["variables"].as_array().unwrap().clone();
+    assert_eq!(variables.len(), 2);
+    assert_eq!(variables[0]["name"], "p");
+    assert_eq!(variables[0]["value"], "our Point");
+    assert_eq!(variables[1]["name"], "n");
+    assert_eq!(variables[1]["value"], "1");
+    assert_eq!(variables[1]["variablesReference"], 0);
+
+    let fields = variables[0]["variablesReference"].clone();
+    let response = session.request("variables", json!({ "variablesReference": fields }));
+    assert_eq!(
+        response["body"]["variables"],
+        json!([
+            { "name": "x", "value": "22", "variablesReference": 0 },
+            { "name": "y", "value": "44", "variablesReference": 0 },
+        ])
+    );
+
+    let response = session.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(response["success"], true);
+
+    // The program is no longer stopped, so it cannot be stepped; the request
+    // fails instead of taking effect at some later stop.
+    let response = session.request("next", json!({ "threadId": 1 }));
+    assert_eq!(response["success"], false);
+
+    assert_eq!(session.event("exited")["exitCode"], 0);
+    session.event("terminated");
+    assert_eq!(session.stdout(), "22\n");
+
+    let response = session.request("stackTrace", json!({ "threadId": 1 }));
+    assert_eq!(response["success"], false);
+    assert_eq!(response["message"], "the program has exited");
+
+    let response = session.request("disconnect", json!({}));
+    assert_eq!(response["success"], true);
+    assert!(session.child.wait().unwrap().success());
+}
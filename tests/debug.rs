@@ -0,0 +1,87 @@
+//! Runs programs under `dada debug`, feeding it commands, and checks what it reports.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Writes `program` to a file named `name` and runs `dada debug` on it with
+/// `args`, typing the commands in `input`. Returns what it wrote to stdout
+/// and stderr, along with the path of the program.
+fn debug(name: &str, program: &str, args: &[&str], input: &str) -> (String, String, String) {
+    let path = std::env::temp_dir().join(format!("dada-debug-test-{name}.dada"));
+    std::fs::write(&path, program).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("debug")
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+        path.display().to_string(),
+    )
+}
+
+#[test]
+fn breakpoint_shows_locals() {
+    let (stdout, stderr, path) = debug(
+        "breakpoint_shows_locals",
+        "\
+class Point(var x, var y)
+
+async fn main() {
+    var p = Point(x: 22, y: 44)
+    var q = p.lease
+    n = 1
+
+    print(m: \"{q.x}\").await
+}
+",
+        &["--break", "7"],
+        "locals\ncontinue\n",
+    );
+
+    // The breakpoint on the blank line moves to the statement after it.
+    assert!(
+        stderr.contains(&format!("stopped in `main` at {path}:8:5\n")),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("p = my Point\nq = leased(p) Point\nn = 1\n"),
+        "{stderr}"
+    );
+    assert_eq!(stdout, "22\n");
+}
+
+#[test]
+fn quit_stops_the_program() {
+    let (stdout, stderr, path) = debug(
+        "quit_stops_the_program",
+        "\
+async fn main() {
+    print(m: \"first\").await
+    print(m: \"second\").await
+}
+",
+        &["--break", "3"],
+        "quit\n",
+    );
+    assert!(
+        stderr.contains(&format!("stopped in `main` at {path}:3:5\n")),
+        "{stderr}"
+    );
+    assert_eq!(stdout, "first\n");
+}
@@ -0,0 +1,465 @@
+//! Checks the control-flow graphs printed by `dada dump --cfg`.
+
+use std::process::Command;
+
+/// Writes `program` to a file named `name` and returns what
+/// `dada dump --cfg` prints for it, with any extra `args`.
+fn dump(name: &str, program: &str, args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("dada-dump-test-{name}.dada"));
+    std::fs::write(&path, program).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("dump")
+        .arg("--cfg")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn if_else() {
+    let cfg = dump(
+        "if_else",
+        "\
+fn choose(a) {
+    if a { 1 } else { 2 }
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn choose {
+    let _0: return;
+    let _1: parameter a;
+    let _2: temporary;
+
+    bb0: {
+        _2 = give _1;
+        if _2 then bb1 else bb2;
+    }
+
+    bb1: {
+        _0 = 1;
+        goto bb3;
+    }
+
+    bb2: {
+        _0 = 2;
+        goto bb3;
+    }
+
+    bb3: {
+        drop _1;
+        return;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn while_loop() {
+    let cfg = dump(
+        "while_loop",
+        "\
+fn count(n) {
+    var i = 0
+    while i < n {
+        i += 1
+    }
+    i
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn count {
+    let _0: return;
+    let _1: parameter n;
+    let _2: temporary;
+    let _3: var i;
+    let _4: temporary;
+    let _5: temporary;
+    let _6: temporary;
+    let _7: temporary;
+    let _8: temporary;
+    let _9: temporary;
+    let _10: temporary;
+    let _11: temporary;
+    let _12: temporary;
+
+    bb0: {
+        _3 = 0;
+        _2 = ();
+        goto bb1;
+    }
+
+    bb1: {
+        _6 = give _3;
+        _7 = give _1;
+        _5 = _6 < _7;
+        if _5 then bb2 else bb3;
+    }
+
+    bb2: {
+        _10 = give _3;
+        _11 = 1;
+        _12 = _10 + _11;
+        _3 = give _12;
+        _9 = ();
+        goto bb1;
+    }
+
+    bb3: {
+        _4 = ();
+        _0 = give _3;
+        drop _1;
+        drop _3;
+        return;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn loop_with_break_value() {
+    // The value of `break` is stored straight into the loop's destination.
+    // The code after the `break` is unreachable, so it gets a block of its own.
+    let cfg = dump(
+        "loop_with_break_value",
+        "\
+fn find(n) {
+    var i = 0
+    loop {
+        if i == n { break i }
+        i += 1
+    }
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn find {
+    let _0: return;
+    let _1: parameter n;
+    let _2: temporary;
+    let _3: var i;
+    let _4: temporary;
+    let _5: temporary;
+    let _6: temporary;
+    let _7: temporary;
+    let _8: temporary;
+    let _9: temporary;
+    let _10: temporary;
+    let _11: temporary;
+
+    bb0: {
+        _3 = 0;
+        _2 = ();
+        goto bb1;
+    }
+
+    bb1: {
+        _7 = give _3;
+        _8 = give _1;
+        _6 = _7 == _8;
+        if _6 then bb3 else bb4;
+    }
+
+    bb2: {
+        drop _1;
+        drop _3;
+        return;
+    }
+
+    bb3: {
+        _0 = give _3;
+        goto bb2;
+    }
+
+    bb4: {
+        _5 = ();
+        goto bb5;
+    }
+
+    bb5: {
+        _9 = give _3;
+        _10 = 1;
+        _11 = _9 + _10;
+        _3 = give _11;
+        _4 = ();
+        goto bb1;
+    }
+
+    bb6: {
+        goto bb5;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn jumps_out_of_atomic() {
+    // Leaving the `atomic` block with `break` or `continue` drops `y` before
+    // the block ends and `x` after it, just as reaching the end of the loop
+    // body does.
+    let cfg = dump(
+        "jumps_out_of_atomic",
+        "\
+fn nested(n) {
+    loop {
+        x = 1
+        atomic {
+            y = 2
+            if n { break x }
+            continue
+        }
+    }
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn nested {
+    let _0: return;
+    let _1: parameter n;
+    let _2: temporary;
+    let _3: temporary;
+    let _4: x;
+    let _5: temporary;
+    let _6: y;
+    let _7: temporary;
+    let _8: temporary;
+
+    bb0: {
+        goto bb1;
+    }
+
+    bb1: {
+        _4 = 1;
+        _3 = ();
+        start atomic;
+        _6 = 2;
+        _5 = ();
+        _8 = give _1;
+        if _8 then bb3 else bb4;
+    }
+
+    bb2: {
+        drop _1;
+        return;
+    }
+
+    bb3: {
+        _0 = give _4;
+        drop _6;
+        end atomic;
+        drop _4;
+        goto bb2;
+    }
+
+    bb4: {
+        _7 = ();
+        goto bb5;
+    }
+
+    bb5: {
+        drop _6;
+        end atomic;
+        drop _4;
+        goto bb1;
+    }
+
+    bb6: {
+        goto bb5;
+    }
+
+    bb7: {
+        drop _6;
+        end atomic;
+        drop _4;
+        goto bb1;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn short_circuit() {
+    // The right operand of `&&` and `||` is only evaluated in its own block.
+    let cfg = dump(
+        "short_circuit",
+        "\
+fn either(a, b, c) {
+    (a && b) || c
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn either {
+    let _0: return;
+    let _1: parameter a;
+    let _2: parameter b;
+    let _3: parameter c;
+    let _4: temporary;
+    let _5: temporary;
+
+    bb0: {
+        _5 = give _1;
+        if _5 then bb1 else bb2;
+    }
+
+    bb1: {
+        _4 = give _2;
+        goto bb3;
+    }
+
+    bb2: {
+        _4 = give _5;
+        goto bb3;
+    }
+
+    bb3: {
+        if _4 then bb5 else bb4;
+    }
+
+    bb4: {
+        _0 = give _3;
+        goto bb6;
+    }
+
+    bb5: {
+        _0 = give _4;
+        goto bb6;
+    }
+
+    bb6: {
+        drop _1;
+        drop _2;
+        drop _3;
+        return;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn return_from_nested_scope() {
+    // `return` drops the variables of every enclosing scope, innermost first.
+    let cfg = dump(
+        "return_from_nested_scope",
+        "\
+fn early(a) {
+    x = 1
+    if a {
+        y = 2
+        return y
+    }
+    x
+}
+",
+        &[],
+    );
+    assert_eq!(
+        cfg,
+        "\
+fn early {
+    let _0: return;
+    let _1: parameter a;
+    let _2: temporary;
+    let _3: x;
+    let _4: temporary;
+    let _5: temporary;
+    let _6: temporary;
+    let _7: y;
+
+    bb0: {
+        _3 = 1;
+        _2 = ();
+        _5 = give _1;
+        if _5 then bb1 else bb2;
+    }
+
+    bb1: {
+        _7 = 2;
+        _6 = ();
+        _0 = give _7;
+        drop _7;
+        drop _1;
+        drop _3;
+        return;
+    }
+
+    bb2: {
+        _4 = ();
+        goto bb3;
+    }
+
+    bb3: {
+        _0 = give _3;
+        drop _1;
+        drop _3;
+        return;
+    }
+
+    bb4: {
+        drop _7;
+        goto bb3;
+    }
+}
+"
+    );
+}
+
+#[test]
+fn graphviz() {
+    let dot = dump(
+        "graphviz",
+        "\
+fn choose(a) {
+    if a { 1 } else { 2 }
+}
+
+fn greet() {
+    \"hi\"
+}
+",
+        &["--dot"],
+    );
+    assert_eq!(
+        dot,
+        r#"digraph "choose" {
+  node [shape = box, fontname = monospace];
+  bb0 [label = "bb0:\l_2 = give _1\lif _2 then bb1 else bb2\l"];
+  bb0 -> bb1 [label = "true"];
+  bb0 -> bb2 [label = "false"];
+  bb1 [label = "bb1:\l_0 = 1\lgoto bb3\l"];
+  bb1 -> bb3;
+  bb2 [label = "bb2:\l_0 = 2\lgoto bb3\l"];
+  bb2 -> bb3;
+  bb3 [label = "bb3:\ldrop _1\lreturn\l"];
+}
+digraph "greet" {
+  node [shape = box, fontname = monospace];
+  bb0 [label = "bb0:\l_0 = \"hi\"\lreturn\l"];
+}
+"#
+    );
+}
@@ -0,0 +1,108 @@
+//! Feeds input to `dada repl` and checks what it prints.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs `dada repl` on `input`, returning what it wrote to stdout and stderr.
+fn repl(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn multi_line_input() {
+    let (stdout, stderr) = repl(
+        "\
+fn add(a, b) {
+    a + b
+}
+add(a: 1, b: 2)
+{
+    x = 20
+    x + 2
+}
+",
+    );
+    assert_eq!(stdout, "3\n22\n");
+    assert!(stderr.contains("  ... "), "{stderr}");
+}
+
+#[test]
+fn unit_values_are_not_printed() {
+    let (stdout, _) = repl(
+        "\
+print(m: \"hello\").await
+x = 22
+1 + 1
+",
+    );
+    assert_eq!(stdout, "hello\n2\n");
+}
+
+#[test]
+fn definitions_persist() {
+    let (stdout, _) = repl(
+        "\
+class Point(var x, var y)
+fn sum(p) {
+    p.x + p.y
+}
+sum(p: Point(x: 1, y: 2))
+sum(p: Point(x: 20, y: 2))
+fn sum(p) {
+    p.x * p.y
+}
+sum(p: Point(x: 20, y: 2))
+",
+    );
+    assert_eq!(stdout, "3\n22\n40\n");
+}
+
+#[test]
+fn main_may_be_defined() {
+    let (stdout, _) = repl(
+        "\
+fn main() {
+    22
+}
+main() + 1
+",
+    );
+    assert_eq!(stdout, "23\n");
+}
+
+#[test]
+fn wrapper_name_is_reserved() {
+    let (stdout, stderr) = repl(
+        "\
+fn __repl_main() {
+    22
+}
+1
+",
+    );
+    assert_eq!(stdout, "1\n");
+    assert!(
+        stderr.contains("`__repl_main` is reserved by the REPL"),
+        "{stderr}"
+    );
+}